@@ -1,157 +1,214 @@
 use std::env;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
+// Shared with the library, which builds the whisper.cpp binary the same way
+#[path = "src/checkout.rs"]
+mod checkout;
+
+use checkout::{WHISPER_CPP_REF_ENV, WHISPER_CPP_REPO_ENV, WHISPER_CPP_VERSION};
+
+// Environment variables selecting how whisper.cpp is found or built
+const ENV_VARS: &[&str] = &[
+    WHISPER_CPP_REF_ENV,
+    WHISPER_CPP_REPO_ENV,
+    "WHISPER_LIB_DIR",
+    "WHISPER_INCLUDE_DIR",
+    "WHISPER_USE_PKG_CONFIG",
+    "WHISPER_SRC_DIR",
+    "PKG_CONFIG_PATH",
+    "DOCS_RS",
+    "WHISPER_DONT_BUILD",
+    "WHISPER_UPDATE_BINDINGS",
+    "WHISPER_USE_PREGENERATED_BINDINGS",
+];
+
+// Bindings generated from the headers of WHISPER_CPP_VERSION, relative to the
+// crate root
+const PREGENERATED_BINDINGS: &str = "bindings/bindings.rs";
+
+// Where the headers are and how to link whisper.cpp
+#[derive(Default)]
+struct Library {
+    // Directories holding whisper.h and ggml.h
+    include_dirs: Vec<PathBuf>,
+
+    // Directories to search for the libraries
+    link_dirs: Vec<PathBuf>,
+
+    // Libraries to link, without prefix or extension
+    link_libs: Vec<String>,
+
+    // The whisper.cpp release such as "1.8.3", if known
+    version: Option<String>,
+}
+
 fn main() {
     // Tell cargo to invalidate the built crate whenever the wrapper changes
     println!("cargo:rerun-if-changed=wrapper.h");
     println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed={}", PREGENERATED_BINDINGS);
 
-    let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
-    let whisper_dir = out_dir.join("whisper.cpp");
-
-    // Clone whisper.cpp if it doesn't exist
-    if !whisper_dir.exists() {
-        println!("Cloning whisper.cpp repository...");
-        let status = Command::new("git")
-            .args(&[
-                "clone",
-                "https://github.com/ggml-org/whisper.cpp",
-                whisper_dir.to_str().unwrap(),
-            ])
-            .status()
-            .expect("Failed to clone whisper.cpp repository");
-
-        if !status.success() {
-            panic!("Failed to clone whisper.cpp repository");
-        }
-    }
-
-    // Build whisper.cpp using CMake
-    println!("Building whisper.cpp...");
-
-    // Configure with CMake
-    let status = Command::new("cmake")
-        .args(&["-B", "build"])
-        .current_dir(&whisper_dir)
-        .status()
-        .expect("Failed to configure whisper.cpp with CMake");
-
-    if !status.success() {
-        panic!("Failed to configure whisper.cpp with CMake");
+    for var in ENV_VARS {
+        println!("cargo:rerun-if-env-changed={}", var);
     }
 
-    // Build with CMake
-    let status = Command::new("cmake")
-        .args(&["--build", "build", "--config", "Release"])
-        .current_dir(&whisper_dir)
-        .status()
-        .expect("Failed to build whisper.cpp with CMake");
+    let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
 
-    if !status.success() {
-        panic!("Failed to build whisper.cpp with CMake");
+    // docs.rs and `cargo check` only need the Rust API, so compile it against
+    // the bindings shipped with the crate without building whisper.cpp
+    if env::var_os("DOCS_RS").is_some() || env::var_os("WHISPER_DONT_BUILD").is_some() {
+        println!("Using the pre-generated bindings without building whisper.cpp");
+        copy_pregenerated_bindings(&out_dir);
+        return;
     }
 
-    // Create wrapper.h
-    let wrapper_path = out_dir.join("wrapper.h");
-
-    // Let's list all files in the whisper.cpp directory to find the header
-    println!("Searching for whisper.h in the repository...");
-    let find_output = Command::new("find")
-        .args(&[
-            whisper_dir.to_str().unwrap(),
-            "-name",
-            "whisper.h",
-            "-type",
-            "f",
-        ])
-        .output()
-        .expect("Failed to execute find command");
+    // A pre-built library is used as is, otherwise whisper.cpp is built from
+    // a local checkout or from the pinned release
+    let library = if let Some(lib_dir) = env::var_os("WHISPER_LIB_DIR") {
+        prebuilt_library(Path::new(&lib_dir))
+    } else if env::var_os("WHISPER_USE_PKG_CONFIG").is_some() {
+        pkg_config_library()
+    } else if let Some(src_dir) = env::var_os("WHISPER_SRC_DIR") {
+        let src_dir = PathBuf::from(src_dir);
+        println!(
+            "cargo:rerun-if-changed={}",
+            src_dir.join("include/whisper.h").display()
+        );
+        println!(
+            "cargo:rerun-if-changed={}",
+            src_dir.join("src/whisper.cpp").display()
+        );
+        build_from_source(&src_dir, &out_dir.join("whisper-build"))
+    } else {
+        // Build a fixed whisper.cpp revision so the bindings match the wrapper code
+        let git_ref = checkout::requested_ref().unwrap_or_else(|| WHISPER_CPP_VERSION.to_string());
+        let whisper_dir = out_dir.join("whisper.cpp");
+        checkout::checkout_whisper_cpp(&whisper_dir, &checkout::whisper_cpp_repo(), &git_ref)
+            .expect("Failed to fetch whisper.cpp");
 
-    let header_paths = String::from_utf8_lossy(&find_output.stdout)
-        .split('\n')
-        .filter(|s| !s.is_empty())
-        .map(|s| PathBuf::from(s))
-        .collect::<Vec<_>>();
+        // Let the library build the binary from the same revision
+        println!("cargo:rustc-env=WHISPER_CPP_BUILD_REF={}", git_ref);
 
-    println!("Found whisper.h files: {:?}", header_paths);
+        let mut library = build_from_source(&whisper_dir, &out_dir.join("whisper-build"));
+        if git_ref == WHISPER_CPP_VERSION {
+            library.version = Some(release(WHISPER_CPP_VERSION).to_string());
+        }
+        library
+    };
 
-    if let Some(header_path) = header_paths.first() {
-        println!("Using whisper.h found at: {:?}", header_path);
+    let header_path = library
+        .include_dirs
+        .iter()
+        .map(|dir| dir.join("whisper.h"))
+        .find(|path| path.is_file());
 
-        fs::write(
-            &wrapper_path,
-            format!(
-                r#"
-#include "{}"
-                "#,
-                header_path.to_str().unwrap()
-            ),
-        )
-        .expect("Failed to write wrapper.h");
+    // The shipped bindings are used for the release they were generated from,
+    // so linking against it doesn't need libclang
+    if use_pregenerated_bindings(&library) {
+        println!(
+            "Using the pre-generated bindings for whisper.cpp {}",
+            WHISPER_CPP_VERSION
+        );
+        copy_pregenerated_bindings(&out_dir);
+    } else if let Some(header_path) = header_path {
+        generate_bindings(&header_path, &library.include_dirs, &out_dir);
     } else {
-        // If we can't find it with find, let's create our own header file
-        println!("No whisper.h found, creating a custom header");
+        println!(
+            "cargo:warning=whisper.h not found in {:?}, using the bindings for whisper.cpp {}",
+            library.include_dirs, WHISPER_CPP_VERSION
+        );
+        copy_pregenerated_bindings(&out_dir);
+    }
 
-        fs::write(
-            &wrapper_path,
-            r#"
-// Whisper API
-#ifdef __cplusplus
-extern "C" {
-#endif
+    // Link with the library
+    for dir in &library.link_dirs {
+        println!("cargo:rustc-link-search=native={}", dir.to_str().unwrap());
+    }
+    for lib in &library.link_libs {
+        println!("cargo:rustc-link-lib={}", lib);
+    }
 
-#include <stdbool.h>
-#include <stdint.h>
-#include <stdio.h>
-#include <stdlib.h>
+    // Libraries the acceleration backends depend on
+    if feature("openblas") {
+        println!("cargo:rustc-link-lib=openblas");
+    }
+    let target = env::var("TARGET").unwrap_or_default();
+    if feature("openmp") && !target.contains("msvc") {
+        let openmp = if target.contains("apple") {
+            "omp"
+        } else {
+            "gomp"
+        };
+        println!("cargo:rustc-link-lib={}", openmp);
+    }
+    if feature("static") && target.contains("apple") {
+        for framework in ["Accelerate", "Foundation", "Metal", "MetalKit"] {
+            println!("cargo:rustc-link-lib=framework={}", framework);
+        }
+    }
+    if let Some(stdlib) = cpp_stdlib(&target) {
+        println!("cargo:rustc-link-lib=dylib={}", stdlib);
+    }
+}
 
-// Basic whisper.cpp API declaration
-typedef struct whisper_context whisper_context;
+// Whether a cargo feature of this crate is enabled
+fn feature(name: &str) -> bool {
+    let var = format!("CARGO_FEATURE_{}", name.to_uppercase().replace('-', "_"));
+    env::var_os(var).is_some()
+}
 
-whisper_context * whisper_init_from_file(const char * path_model);
-void whisper_free(whisper_context * ctx);
+// Whether to use the shipped bindings rather than generate them from the
+// headers: when asked to, or when the library is the release they were
+// generated from, unless they are being refreshed
+fn use_pregenerated_bindings(library: &Library) -> bool {
+    if env::var_os("WHISPER_UPDATE_BINDINGS").is_some() {
+        return false;
+    }
+    env::var_os("WHISPER_USE_PREGENERATED_BINDINGS").is_some()
+        || library.version.as_deref() == Some(release(WHISPER_CPP_VERSION))
+}
 
-#ifdef __cplusplus
+// The release of a whisper.cpp tag, such as "1.8.3" for "v1.8.3"
+fn release(tag: &str) -> &str {
+    tag.trim_start_matches('v')
 }
-#endif
-            "#,
-        )
-        .expect("Failed to write custom wrapper.h");
-    }
-
-    // Find ggml.h
-    println!("Searching for ggml.h in the repository...");
-    let ggml_find_output = Command::new("find")
-        .args(&[
-            whisper_dir.to_str().unwrap(),
-            "-name",
-            "ggml.h",
-            "-type",
-            "f",
-        ])
-        .output()
-        .expect("Failed to execute find command for ggml.h");
 
-    let ggml_paths = String::from_utf8_lossy(&ggml_find_output.stdout)
-        .split('\n')
-        .filter(|s| !s.is_empty())
-        .map(|s| PathBuf::from(s))
-        .collect::<Vec<_>>();
+// Generate bindings for `header` into $OUT_DIR/bindings.rs
+#[cfg(feature = "bindgen")]
+fn generate_bindings(header: &Path, include_dirs: &[PathBuf], out_dir: &Path) {
+    println!("Using whisper.h found at: {:?}", header);
 
-    println!("Found ggml.h files: {:?}", ggml_paths);
+    // Create wrapper.h
+    let wrapper_path = out_dir.join("wrapper.h");
+    fs::write(
+        &wrapper_path,
+        format!(
+            r#"
+#include "{}"
+            "#,
+            header.to_str().unwrap()
+        ),
+    )
+    .expect("Failed to write wrapper.h");
 
-    // Generate bindings
+    // Only the whisper.cpp and ggml API, without layout tests, so the shipped
+    // bindings don't carry libc items or pointer sizes of the generating host.
+    // ggml_fopen is skipped as it is the only function using the libc FILE.
     let mut builder = bindgen::Builder::default()
         .header(wrapper_path.to_str().unwrap())
+        .allowlist_function("whisper_.*|ggml_.*")
+        .allowlist_type("whisper_.*|ggml_.*")
+        .allowlist_var("WHISPER_.*|GGML_.*")
+        .blocklist_function("ggml_fopen")
+        .layout_tests(false)
         .parse_callbacks(Box::new(bindgen::CargoCallbacks::new()));
 
     // Add include paths for ggml.h
-    if let Some(ggml_path) = ggml_paths.first() {
-        let ggml_dir = ggml_path.parent().unwrap();
-        println!("Adding include path: {:?}", ggml_dir);
-        builder = builder.clang_arg(format!("-I{}", ggml_dir.to_str().unwrap()));
+    for dir in include_dirs {
+        println!("Adding include path: {:?}", dir);
+        builder = builder.clang_arg(format!("-I{}", dir.to_str().unwrap()));
     }
 
     let bindings = builder.generate().expect("Unable to generate bindings");
@@ -159,19 +216,217 @@ void whisper_free(whisper_context * ctx);
     // Write the bindings to the $OUT_DIR/bindings.rs file
     let bindings_path = out_dir.join("bindings.rs");
     bindings
-        .write_to_file(bindings_path)
+        .write_to_file(&bindings_path)
         .expect("Couldn't write bindings!");
 
-    // Link with the built library
-    println!(
-        "cargo:rustc-link-search={}/build/src",
-        whisper_dir.to_str().unwrap()
-    );
-    println!(
-        "cargo:rustc-link-search={}/build/ggml/src",
-        whisper_dir.to_str().unwrap()
+    // Refresh the shipped bindings after changing WHISPER_CPP_VERSION
+    if env::var_os("WHISPER_UPDATE_BINDINGS").is_some() {
+        fs::copy(&bindings_path, manifest_dir().join(PREGENERATED_BINDINGS))
+            .expect("Failed to update the pre-generated bindings");
+    }
+}
+
+// Without bindgen, only the shipped bindings can be used, and only with the
+// release they were generated from
+#[cfg(not(feature = "bindgen"))]
+fn generate_bindings(header: &Path, _include_dirs: &[PathBuf], _out_dir: &Path) {
+    panic!(
+        "{:?} is not from whisper.cpp {}, which the shipped bindings are for. Enable the \
+         `bindgen` feature to generate bindings, or set WHISPER_USE_PREGENERATED_BINDINGS \
+         if its API is the same",
+        header, WHISPER_CPP_VERSION
     );
-    println!("cargo:rustc-link-lib=whisper");
-    println!("cargo:rustc-link-lib=ggml");
-    println!("cargo:rustc-link-lib=dylib=stdc++");
+}
+
+// Use the bindings shipped with the crate
+fn copy_pregenerated_bindings(out_dir: &Path) {
+    fs::copy(
+        manifest_dir().join(PREGENERATED_BINDINGS),
+        out_dir.join("bindings.rs"),
+    )
+    .expect("Failed to copy the pre-generated bindings");
+}
+
+fn manifest_dir() -> PathBuf {
+    PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap())
+}
+
+// Use a libwhisper installed or built elsewhere, from `WHISPER_LIB_DIR` and
+// optionally `WHISPER_INCLUDE_DIR`
+fn prebuilt_library(lib_dir: &Path) -> Library {
+    println!("Using the whisper.cpp library in {:?}", lib_dir);
+
+    let include_dirs = match env::var_os("WHISPER_INCLUDE_DIR") {
+        Some(dir) => env::split_paths(&dir).collect(),
+        // Installed layouts put the headers next to the lib directory
+        None => match lib_dir.parent() {
+            Some(prefix) => vec![prefix.join("include"), lib_dir.to_path_buf()],
+            None => vec![lib_dir.to_path_buf()],
+        },
+    };
+
+    // Releases since 1.7 split ggml into several libraries, older ones
+    // include it in libwhisper
+    let kind = if feature("static") { "static=" } else { "" };
+    let mut link_libs = vec![format!("{}whisper", kind)];
+    for lib in ["ggml", "ggml-cpu", "ggml-blas", "ggml-base"] {
+        if has_library(lib_dir, lib) {
+            link_libs.push(format!("{}{}", kind, lib));
+        }
+    }
+
+    // whisper.cpp installs a pkg-config file with the release next to the
+    // libraries
+    let version = fs::read_to_string(lib_dir.join("pkgconfig/whisper.pc"))
+        .ok()
+        .and_then(|pc| {
+            pc.lines()
+                .find_map(|line| line.strip_prefix("Version:"))
+                .map(|version| version.trim().to_string())
+        });
+
+    Library {
+        include_dirs,
+        link_dirs: vec![lib_dir.to_path_buf()],
+        link_libs,
+        version,
+    }
+}
+
+// Whether a directory holds a static or shared library with this name
+fn has_library(dir: &Path, name: &str) -> bool {
+    [
+        format!("lib{}.a", name),
+        format!("lib{}.so", name),
+        format!("lib{}.dylib", name),
+        format!("{}.lib", name),
+    ]
+    .iter()
+    .any(|file| dir.join(file).exists())
+}
+
+// Use the `whisper` package from pkg-config
+fn pkg_config_library() -> Library {
+    println!("Looking up whisper with pkg-config...");
+
+    let output = Command::new("pkg-config")
+        .args(["--cflags", "--libs", "whisper"])
+        .args(feature("static").then_some("--static"))
+        .output()
+        .expect("Failed to run pkg-config");
+
+    if !output.status.success() {
+        panic!(
+            "pkg-config could not find whisper: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    let mut library = Library::default();
+    for flag in String::from_utf8_lossy(&output.stdout).split_whitespace() {
+        if let Some(dir) = flag.strip_prefix("-I") {
+            library.include_dirs.push(PathBuf::from(dir));
+        } else if let Some(dir) = flag.strip_prefix("-L") {
+            library.link_dirs.push(PathBuf::from(dir));
+        } else if let Some(lib) = flag.strip_prefix("-l") {
+            library.link_libs.push(lib.to_string());
+        }
+    }
+
+    library.version = Command::new("pkg-config")
+        .args(["--modversion", "whisper"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string());
+
+    // pkg-config leaves out the default include directory
+    library.include_dirs.push(PathBuf::from("/usr/include"));
+    library
+        .include_dirs
+        .push(PathBuf::from("/usr/local/include"));
+    library
+}
+
+// Build whisper.cpp from the sources in `src_dir` using CMake and install it
+// into `dst`
+fn build_from_source(src_dir: &Path, dst: &Path) -> Library {
+    if !src_dir.join("CMakeLists.txt").is_file() {
+        panic!("No whisper.cpp sources found in {:?}", src_dir);
+    }
+
+    println!("Building whisper.cpp...");
+
+    // Set the options the features control explicitly rather than relying on
+    // upstream defaults, which change between releases. GGML_NATIVE is the
+    // exception: upstream enables it for native builds and turns it off when
+    // cross-compiling, and turning it off here would leave x86_64 builds
+    // without AVX. The cmake crate takes care of the target, toolchain and
+    // parallel jobs.
+    let on_off = |enabled: bool| if enabled { "ON" } else { "OFF" };
+    let target = env::var("TARGET").unwrap_or_default();
+    let apple = target.contains("apple");
+    // Apple platforms get Accelerate through the BLAS backend, and Metal
+    let blas = feature("openblas") || apple;
+
+    let mut config = cmake::Config::new(src_dir);
+    config
+        .out_dir(dst)
+        .profile(cmake_profile())
+        .define("BUILD_SHARED_LIBS", on_off(!feature("static")))
+        .define("GGML_OPENMP", on_off(feature("openmp")))
+        .define("GGML_BLAS", on_off(blas))
+        .define("GGML_METAL", on_off(apple))
+        .define("WHISPER_BUILD_TESTS", "OFF")
+        // GNUInstallDirs would use lib64 on some distributions
+        .define("CMAKE_INSTALL_LIBDIR", "lib");
+    if feature("native-cpu") {
+        config.define("GGML_NATIVE", "ON");
+    }
+    if feature("openblas") {
+        config.define("GGML_BLAS_VENDOR", "OpenBLAS");
+    }
+    let dst = config.build();
+
+    // Link every library explicitly, dependents first, so the result
+    // doesn't depend on what else is installed
+    let mut libs = vec!["whisper", "ggml", "ggml-cpu"];
+    if blas {
+        libs.push("ggml-blas");
+    }
+    if apple {
+        libs.push("ggml-metal");
+    }
+    libs.push("ggml-base");
+
+    let kind = if feature("static") { "static" } else { "dylib" };
+    Library {
+        include_dirs: vec![dst.join("include")],
+        link_dirs: vec![dst.join("lib")],
+        link_libs: libs.iter().map(|lib| format!("{}={}", kind, lib)).collect(),
+        // A checkout may be anywhere between releases
+        version: None,
+    }
+}
+
+// CMake build type for the cargo profile. Unoptimized whisper.cpp is too slow
+// to use, so debug builds keep optimizations and add debug info.
+fn cmake_profile() -> &'static str {
+    match env::var("DEBUG").as_deref() {
+        Ok("true") => "RelWithDebInfo",
+        _ => "Release",
+    }
+}
+
+// The C++ standard library whisper.cpp needs, if it isn't linked by default
+fn cpp_stdlib(target: &str) -> Option<&'static str> {
+    if target.contains("msvc") {
+        None
+    } else if target.contains("apple") || target.contains("freebsd") || target.contains("openbsd") {
+        Some("c++")
+    } else if target.contains("android") {
+        Some("c++_shared")
+    } else {
+        Some("stdc++")
+    }
 }
@@ -5,7 +5,7 @@ use whisper_wrapper_rust::{WhisperContext, WhisperParams};
 fn main() -> Result<(), Box<dyn Error>> {
     // Download the base model (requires the "download" feature to be enabled)
     #[cfg(feature = "download")]
-    let model_path = whisper_wrapper_rust::download_model("base")?;
+    let model_path = whisper_wrapper_rust::download_model(whisper_wrapper_rust::Model::Base)?;
 
     #[cfg(not(feature = "download"))]
     let model_path = {
@@ -19,7 +19,7 @@ fn main() -> Result<(), Box<dyn Error>> {
     let mut ctx = WhisperContext::new(&model_path)?;
 
     // Path to your audio file
-    let audio_path = Path::new("path/to/your/audio.mp3");
+    let audio_path = Path::new("path/to/your/audio.wav");
 
     // Check if the audio file exists
     if !audio_path.exists() {
@@ -41,14 +41,15 @@ fn main() -> Result<(), Box<dyn Error>> {
 
     // Save the result to a file
     let output_path = "transcription.srt";
-    std::fs::write(output_path, &result)?;
+    let srt = result.format(params.get_output_format())?;
+    std::fs::write(output_path, &srt)?;
 
     println!("Transcription complete!");
     println!("Result saved to: {}", output_path);
     println!("First few lines:");
 
     // Print the first few lines of the result
-    let preview = result.lines().take(10).collect::<Vec<_>>().join("\n");
+    let preview = srt.lines().take(10).collect::<Vec<_>>().join("\n");
     println!("{}", preview);
 
     Ok(())
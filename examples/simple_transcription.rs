@@ -5,14 +5,14 @@ use whisper_wrapper_rust::{WhisperContext, WhisperParams};
 fn main() -> Result<(), Box<dyn Error>> {
     // Path to your model file
     // If you have the download feature enabled, you can download it:
-    // let model_path = whisper_wrapper_rust::download_model("base")?;
+    // let model_path = whisper_wrapper_rust::download_model(whisper_wrapper_rust::Model::Base)?;
     let model_path = Path::new("path/to/your/model.bin");
 
     // Create a whisper context
     let mut ctx = WhisperContext::new(model_path)?;
 
     // Path to your audio file
-    let audio_path = Path::new("path/to/your/audio.mp3");
+    let audio_path = Path::new("path/to/your/audio.wav");
 
     // Set parameters
     let params = WhisperParams::new()
@@ -25,7 +25,7 @@ fn main() -> Result<(), Box<dyn Error>> {
 
     // Print the result
     println!("Transcription result:");
-    println!("{}", result);
+    println!("{}", result.format(params.get_output_format())?);
 
     Ok(())
 }
@@ -0,0 +1,253 @@
+use std::path::{Path, PathBuf};
+
+use tokio::fs::{self, File, OpenOptions};
+use tokio::io::{AsyncWriteExt, BufWriter};
+use tokio::sync::mpsc::Sender;
+use tokio::time;
+
+use crate::cache::{is_offline, ModelCache};
+use crate::download::{
+    check_status, finish_download, is_resumed, is_retryable, part_path, DownloadConfig,
+    DownloadProgress, ModelSources,
+};
+use crate::error::WhisperError;
+use crate::model::Model;
+use crate::Result;
+
+// Run blocking file work, such as hashing or updating the cache index, on the
+// blocking pool so it doesn't stall the runtime
+async fn blocking<T, F>(f: F) -> Result<T>
+where
+    F: FnOnce() -> Result<T> + Send + 'static,
+    T: Send + 'static,
+{
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(|e| WhisperError::DownloadError(format!("Download task failed: {}", e)))?
+}
+
+// Report progress without ever waiting on a slow receiver
+fn report(progress: Option<&Sender<DownloadProgress>>, update: DownloadProgress) {
+    if let Some(tx) = progress {
+        let _ = tx.try_send(update);
+    }
+}
+
+/// Download a URL to a file without blocking the async runtime
+///
+/// This is the async counterpart of [`download_url`](crate::download_url):
+/// data is streamed to `<path>.part`, resumed with a Range request if a
+/// previous attempt was interrupted, and renamed to `path` once complete.
+///
+/// Dropping the returned future cancels the download, leaving the `.part`
+/// file behind to be resumed later. Progress updates are sent through
+/// `progress` with `try_send`, so they are skipped rather than slowing the
+/// download when the receiver falls behind.
+pub async fn download_url_async(
+    url: &str,
+    path: &Path,
+    config: &DownloadConfig,
+    progress: Option<&Sender<DownloadProgress>>,
+) -> Result<()> {
+    if is_offline() {
+        return Err(WhisperError::Offline(url.to_string()));
+    }
+
+    let client = config.async_client()?;
+    download_with_retries(&client, config, url, path, None, progress).await
+}
+
+// Download a file, retrying with exponential backoff and resuming from the
+// partial file left by the previous attempt
+async fn download_with_retries(
+    client: &reqwest::Client,
+    config: &DownloadConfig,
+    url: &str,
+    path: &Path,
+    sha1: Option<&str>,
+    progress: Option<&Sender<DownloadProgress>>,
+) -> Result<()> {
+    let mut attempt = 0;
+
+    loop {
+        match download_file(client, config, url, path, sha1, progress).await {
+            Err(e) if attempt < config.get_retries() && is_retryable(&e) => {
+                attempt += 1;
+                let delay = config.backoff_for(attempt);
+                log::warn!(
+                    "Download of {} failed ({}), retrying in {:?} ({}/{})",
+                    url,
+                    e,
+                    delay,
+                    attempt,
+                    config.get_retries()
+                );
+                time::sleep(delay).await;
+            }
+            result => return result,
+        }
+    }
+}
+
+async fn download_file(
+    client: &reqwest::Client,
+    config: &DownloadConfig,
+    url: &str,
+    path: &Path,
+    sha1: Option<&str>,
+    progress: Option<&Sender<DownloadProgress>>,
+) -> Result<()> {
+    let part_path = part_path(path);
+    let mut existing = fs::metadata(&part_path).await.map(|m| m.len()).unwrap_or(0);
+
+    let request = |range_start: u64| {
+        let mut request = client.get(url);
+        if range_start > 0 {
+            request = request.header(reqwest::header::RANGE, format!("bytes={}-", range_start));
+        }
+        if let Some(token) = config.token_for(url) {
+            request = request.bearer_auth(token);
+        }
+        request
+    };
+
+    // The client has no overall timeout, so bound the wait for the headers
+    // the same way as each chunk of the body
+    let send = |request: reqwest::RequestBuilder| async move {
+        match time::timeout(config.get_read_timeout(), request.send()).await {
+            Ok(resp) => Ok(resp?),
+            Err(_) => Err(WhisperError::DownloadError(format!(
+                "No response from {} within {:?}",
+                url,
+                config.get_read_timeout()
+            ))),
+        }
+    };
+
+    let mut resp = send(request(existing)).await?;
+
+    // The partial file is already complete or larger than the remote file,
+    // which can only be fixed by starting over
+    if resp.status() == reqwest::StatusCode::RANGE_NOT_SATISFIABLE {
+        fs::remove_file(&part_path).await?;
+        existing = 0;
+        resp = send(request(0)).await?;
+    }
+
+    check_status(url, resp.status())?;
+    let resume = is_resumed(resp.status(), resp.headers(), existing);
+
+    let mut downloaded = if resume { existing } else { 0 };
+    let total = resp.content_length().map(|len| len + downloaded);
+
+    let file = if resume {
+        OpenOptions::new().append(true).open(&part_path).await?
+    } else {
+        File::create(&part_path).await?
+    };
+    let mut file = BufWriter::new(file);
+
+    report(progress, DownloadProgress { downloaded, total });
+
+    loop {
+        let chunk = match time::timeout(config.get_read_timeout(), resp.chunk()).await {
+            Ok(Ok(Some(chunk))) => chunk,
+            Ok(Ok(None)) => break,
+            Ok(Err(e)) => {
+                file.flush().await?;
+                return Err(WhisperError::DownloadError(format!(
+                    "Download of {} interrupted after {} bytes: {}",
+                    url, downloaded, e
+                )));
+            }
+            Err(_) => {
+                file.flush().await?;
+                return Err(WhisperError::DownloadError(format!(
+                    "Download of {} stalled after {} bytes",
+                    url, downloaded
+                )));
+            }
+        };
+
+        file.write_all(&chunk).await?;
+        downloaded += chunk.len() as u64;
+        report(progress, DownloadProgress { downloaded, total });
+    }
+
+    file.flush().await?;
+    file.get_ref().sync_all().await?;
+    drop(file);
+
+    // Hashing a multi-gigabyte model would stall the runtime
+    let url = url.to_string();
+    let path = path.to_path_buf();
+    let sha1 = sha1.map(|s| s.to_string());
+    blocking(move || finish_download(&url, &path, downloaded, total, sha1.as_deref())).await
+}
+
+/// Download a Whisper model without blocking the async runtime
+///
+/// Shares the model registry, mirrors, cache directory, checksum
+/// verification and offline mode with [`download_model`](crate::download_model),
+/// and takes the same network settings as
+/// [`download_model_with_config`](crate::download_model_with_config).
+///
+/// # Examples
+///
+/// ```no_run
+/// use whisper_wrapper_rust::{download_model_async, DownloadConfig, DownloadProgress, Model};
+///
+/// # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+/// let (tx, mut rx) = tokio::sync::mpsc::channel::<DownloadProgress>(16);
+///
+/// tokio::spawn(async move {
+///     while let Some(p) = rx.recv().await {
+///         println!("{} bytes", p.downloaded);
+///     }
+/// });
+///
+/// let config = DownloadConfig::default();
+/// let model_path = download_model_async(Model::Base, &config, Some(tx)).await?;
+/// println!("Model downloaded to {:?}", model_path);
+/// # Ok(())
+/// # }
+/// ```
+pub async fn download_model_async(
+    model: Model,
+    config: &DownloadConfig,
+    progress: Option<Sender<DownloadProgress>>,
+) -> Result<PathBuf> {
+    // Opening the cache may create the models directory
+    let cache = blocking(ModelCache::new).await?;
+    let model_path = cache.dir().join(model.filename());
+
+    if !fs::try_exists(&model_path).await? {
+        if is_offline() {
+            return Err(WhisperError::Offline(model.name().to_string()));
+        }
+
+        let client = config.async_client()?;
+        let mut sources = ModelSources::new(model);
+        loop {
+            let Some(url) = sources.next_url() else {
+                return Err(sources.into_error());
+            };
+            match download_with_retries(
+                &client,
+                config,
+                &url,
+                &model_path,
+                Some(sources.sha1()),
+                progress.as_ref(),
+            )
+            .await
+            {
+                Ok(()) => break,
+                Err(e) => sources.failed(&url, e),
+            }
+        }
+    }
+
+    blocking(move || cache.touch(model.name())).await?;
+    Ok(model_path)
+}
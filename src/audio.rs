@@ -0,0 +1,120 @@
+use std::fs;
+use std::path::Path;
+
+use crate::bindings;
+use crate::error::WhisperError;
+use crate::Result;
+
+// WAVE format tags
+const FORMAT_PCM: u16 = 1;
+const FORMAT_FLOAT: u16 = 3;
+const FORMAT_EXTENSIBLE: u16 = 0xFFFE;
+
+/// Read a WAV file into the samples whisper.cpp expects
+///
+/// The file must be 16 kHz 16-bit PCM or 32-bit float. Stereo and
+/// multi-channel audio is mixed down to mono. Other formats can be converted
+/// with, for example, `ffmpeg -i input.mp3 -ar 16000 -ac 1 output.wav`.
+pub fn read_wav(path: &Path) -> Result<Vec<f32>> {
+    if !path.exists() {
+        return Err(WhisperError::AudioNotFound(path.to_path_buf()));
+    }
+
+    parse_wav(&fs::read(path)?)
+}
+
+fn unsupported(message: &str) -> WhisperError {
+    WhisperError::UnsupportedAudioFormat(message.to_string())
+}
+
+fn u16_at(data: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([data[offset], data[offset + 1]])
+}
+
+fn u32_at(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([
+        data[offset],
+        data[offset + 1],
+        data[offset + 2],
+        data[offset + 3],
+    ])
+}
+
+fn parse_wav(data: &[u8]) -> Result<Vec<f32>> {
+    if data.len() < 12 || &data[0..4] != b"RIFF" || &data[8..12] != b"WAVE" {
+        return Err(unsupported("not a WAV file (only WAV audio is supported)"));
+    }
+
+    // (format, channels, sample rate, bits per sample)
+    let mut format = None;
+    let mut samples = None;
+
+    // Walk the chunks; their sizes are padded to an even number of bytes.
+    // Streamed files give 0xFFFFFFFF as the size of the last chunk, which
+    // then runs to the end of the file.
+    let mut offset = 12;
+    while offset + 8 <= data.len() {
+        let id = &data[offset..offset + 4];
+        let size = u32_at(data, offset + 4) as usize;
+        let end = (offset + 8).checked_add(size);
+        let body = &data[offset + 8..end.map_or(data.len(), |end| end.min(data.len()))];
+
+        match id {
+            b"fmt " if body.len() >= 16 => {
+                let mut tag = u16_at(body, 0);
+                if tag == FORMAT_EXTENSIBLE && body.len() >= 26 {
+                    // The actual format is the start of the sub-format GUID
+                    tag = u16_at(body, 24);
+                }
+                format = Some((tag, u16_at(body, 2), u32_at(body, 4), u16_at(body, 14)));
+            }
+            b"data" => samples = Some(body),
+            _ => {}
+        }
+
+        match end.and_then(|end| end.checked_add(size % 2)) {
+            Some(next) => offset = next,
+            None => break,
+        }
+    }
+
+    let (tag, channels, rate, bits) = format.ok_or_else(|| unsupported("missing fmt chunk"))?;
+    let samples = samples.ok_or_else(|| unsupported("missing data chunk"))?;
+
+    if rate != bindings::WHISPER_SAMPLE_RATE {
+        return Err(unsupported(&format!(
+            "sample rate is {} Hz, expected {} Hz",
+            rate,
+            bindings::WHISPER_SAMPLE_RATE
+        )));
+    }
+    if channels == 0 {
+        return Err(unsupported("no audio channels"));
+    }
+
+    let mono: Vec<f32> = match (tag, bits) {
+        (FORMAT_PCM, 16) => samples
+            .chunks_exact(2)
+            .map(|b| i16::from_le_bytes([b[0], b[1]]) as f32 / 32768.0)
+            .collect(),
+        (FORMAT_FLOAT, 32) => samples
+            .chunks_exact(4)
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .collect(),
+        _ => {
+            return Err(unsupported(&format!(
+            "{}-bit samples with format tag {} (only 16-bit PCM and 32-bit float are supported)",
+            bits, tag
+        )))
+        }
+    };
+
+    if channels == 1 {
+        return Ok(mono);
+    }
+
+    Ok(mono
+        .chunks_exact(channels as usize)
+        .map(|frame| frame.iter().sum::<f32>() / channels as f32)
+        .collect())
+}
@@ -0,0 +1,485 @@
+use sha1::{Digest, Sha1};
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::env;
+use std::ffi::OsStr;
+use std::fs::{self, File};
+use std::io::{BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::RwLock;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::error::WhisperError;
+use crate::model::{Model, Quantization};
+use crate::Result;
+
+/// Environment variable overriding the model cache directory
+pub const MODELS_DIR_ENV: &str = "WHISPER_MODELS_DIR";
+
+// Directory set through `set_models_dir`, which takes precedence over the environment
+static MODELS_DIR: RwLock<Option<PathBuf>> = RwLock::new(None);
+
+/// Environment variable that disables model downloads when set to `1` or `true`
+pub const OFFLINE_ENV: &str = "WHISPER_OFFLINE";
+
+// Offline mode set through `set_offline`, in addition to the environment
+static OFFLINE: AtomicBool = AtomicBool::new(false);
+
+// File in the cache directory recording when each model was last used
+const INDEX_FILE: &str = ".whisper-cache.json";
+
+/// Set the directory models are cached in for the rest of the process
+///
+/// Passing `None` restores the default lookup.
+pub fn set_models_dir<P: AsRef<Path>>(dir: Option<P>) {
+    let mut models_dir = MODELS_DIR.write().unwrap_or_else(|e| e.into_inner());
+    *models_dir = dir.map(|d| d.as_ref().to_path_buf());
+}
+
+// Resolve the models directory without creating it
+fn resolve_models_dir() -> PathBuf {
+    if let Some(dir) = MODELS_DIR.read().unwrap_or_else(|e| e.into_inner()).clone() {
+        return dir;
+    }
+
+    if let Some(dir) = env::var_os(MODELS_DIR_ENV).filter(|d| !d.is_empty()) {
+        return PathBuf::from(dir);
+    }
+
+    // Keep using the directory from older versions so models aren't downloaded again
+    if let Some(legacy) = dirs::home_dir().map(|home| home.join(".whisper-models")) {
+        if legacy.is_dir() {
+            return legacy;
+        }
+    }
+
+    dirs::cache_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("whisper-models")
+}
+
+/// Get the directory models are cached in, creating it if needed
+///
+/// The directory is, in order of precedence:
+///
+/// 1. the directory passed to [`set_models_dir`]
+/// 2. the `WHISPER_MODELS_DIR` environment variable
+/// 3. `~/.whisper-models`, if it already exists
+/// 4. `whisper-models` in the user's cache directory (e.g. `$XDG_CACHE_HOME`)
+pub fn models_dir() -> Result<PathBuf> {
+    let models_dir = resolve_models_dir();
+
+    if !models_dir.exists() {
+        fs::create_dir_all(&models_dir)?;
+    }
+
+    Ok(models_dir)
+}
+
+/// Enable or disable offline mode for the rest of the process
+///
+/// In offline mode, models are only ever loaded from the cache and no
+/// network requests are made.
+pub fn set_offline(offline: bool) {
+    OFFLINE.store(offline, Ordering::SeqCst);
+}
+
+/// Whether offline mode is enabled, either through [`set_offline`] or `WHISPER_OFFLINE`
+pub fn is_offline() -> bool {
+    if OFFLINE.load(Ordering::SeqCst) {
+        return true;
+    }
+
+    env::var(OFFLINE_ENV)
+        .map(|v| matches!(v.trim().to_ascii_lowercase().as_str(), "1" | "true" | "yes"))
+        .unwrap_or(false)
+}
+
+/// Resolve a model path or name to a model file
+///
+/// `model` can be a path to a model file, or the name of a model such as
+/// "base.en" or a custom model downloaded with `download_custom_model`.
+/// Names are looked up in the cache first. Registry models that are not
+/// cached are downloaded, unless offline mode is enabled or the crate was
+/// built without the `download` feature, in which case an
+/// `WhisperError::Offline` error is returned without touching the network.
+///
+/// # Examples
+///
+/// ```no_run
+/// use whisper_wrapper_rust::{resolve_model, WhisperContext};
+///
+/// fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let model_path = resolve_model("base.en")?;
+///     let ctx = WhisperContext::new(&model_path)?;
+///     Ok(())
+/// }
+/// ```
+pub fn resolve_model(model: &str) -> Result<PathBuf> {
+    let path = Path::new(model);
+    if path.is_file() {
+        return Ok(path.to_path_buf());
+    }
+
+    // Anything that looks like a path is not treated as a model name
+    if model.contains(['/', '\\']) || model.ends_with(".bin") {
+        return Err(WhisperError::ModelNotFound(path.to_path_buf()));
+    }
+
+    let cache = ModelCache::new()?;
+    let registry_model = model.parse::<Model>().ok();
+    let name = registry_model.map_or(model, |m| m.name());
+
+    if let Some(cached) = cache.get(name)? {
+        cache.touch(&cached.name)?;
+        return Ok(cached.path);
+    }
+
+    let registry_model =
+        registry_model.ok_or_else(|| WhisperError::UnknownModel(model.to_string()))?;
+
+    #[cfg(feature = "download")]
+    if !is_offline() {
+        return crate::download::download_model(registry_model);
+    }
+
+    Err(WhisperError::Offline(registry_model.name().to_string()))
+}
+
+// Compute the SHA-1 digest of a file as a lowercase hex string
+pub(crate) fn sha1_file(path: &Path) -> Result<String> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut hasher = Sha1::new();
+    let mut buf = vec![0u8; 64 * 1024];
+
+    loop {
+        let n = reader.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect())
+}
+
+/// Verify a model file against the digest published for the model
+///
+/// Returns `WhisperError::ChecksumMismatch` if the file does not match.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::path::Path;
+/// use whisper_wrapper_rust::{verify_model, Model};
+///
+/// fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     verify_model(Model::Base, Path::new("models/ggml-base.bin"))?;
+///     Ok(())
+/// }
+/// ```
+pub fn verify_model(model: Model, path: &Path) -> Result<()> {
+    if !path.exists() {
+        return Err(WhisperError::ModelNotFound(path.to_path_buf()));
+    }
+
+    let actual = sha1_file(path)?;
+    if actual != model.sha1() {
+        return Err(WhisperError::ChecksumMismatch {
+            path: path.to_path_buf(),
+            expected: model.sha1().to_string(),
+            actual,
+        });
+    }
+
+    Ok(())
+}
+
+/// Result of checking a cached model against its published checksum
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChecksumStatus {
+    /// The file matches the published digest
+    Valid,
+
+    /// The file does not match the published digest
+    Mismatch,
+
+    /// The model is not in the registry, so there is nothing to check against
+    Unknown,
+}
+
+/// A model file in the cache
+#[derive(Debug, Clone)]
+pub struct CachedModel {
+    /// The model name (e.g. "base.en-q5_1")
+    pub name: String,
+
+    /// The path to the model file
+    pub path: PathBuf,
+
+    /// The size of the file in bytes
+    pub size: u64,
+
+    /// The registry entry for the model, if it is a known model
+    pub model: Option<Model>,
+
+    /// When the model was last used, falling back to when it was written
+    pub last_used: Option<SystemTime>,
+}
+
+impl CachedModel {
+    /// Get the quantization of the model, if known
+    pub fn quantization(&self) -> Option<Quantization> {
+        self.model.and_then(|m| m.quantization())
+    }
+}
+
+/// The directory downloaded models are stored in
+///
+/// Besides listing and removing models, the cache keeps track of when each
+/// model was last used so it can be pruned to a size budget.
+///
+/// # Examples
+///
+/// ```no_run
+/// use whisper_wrapper_rust::ModelCache;
+///
+/// fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let cache = ModelCache::new()?;
+///     for model in cache.list()? {
+///         println!("{} ({} bytes)", model.name, model.size);
+///     }
+///
+///     // Keep at most 2 GiB of models, removing the least recently used first
+///     cache.prune(2 * 1024 * 1024 * 1024)?;
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ModelCache {
+    dir: PathBuf,
+}
+
+impl ModelCache {
+    /// Open the cache in the configured models directory
+    pub fn new() -> Result<Self> {
+        Ok(Self { dir: models_dir()? })
+    }
+
+    /// Open a cache in a specific directory
+    pub fn with_dir<P: AsRef<Path>>(dir: P) -> Self {
+        Self {
+            dir: dir.as_ref().to_path_buf(),
+        }
+    }
+
+    /// Get the cache directory
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// List the models in the cache, most recently used first
+    pub fn list(&self) -> Result<Vec<CachedModel>> {
+        if !self.dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let index = self.read_index();
+        let mut models = Vec::new();
+
+        for entry in fs::read_dir(&self.dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            if !path.is_file() || path.extension() != Some(OsStr::new("bin")) {
+                continue;
+            }
+
+            let file_name = entry.file_name().to_string_lossy().into_owned();
+            let name = file_name
+                .strip_prefix("ggml-")
+                .unwrap_or(&file_name)
+                .trim_end_matches(".bin")
+                .to_string();
+            let metadata = entry.metadata()?;
+
+            let last_used = index
+                .get(&file_name)
+                .map(|ms| UNIX_EPOCH + Duration::from_millis(*ms))
+                .or_else(|| metadata.modified().ok());
+
+            // Match registry names exactly so aliases like "large" aren't
+            // mistaken for a specific model
+            let model = Model::all().find(|m| m.name() == name);
+
+            models.push(CachedModel {
+                model,
+                name,
+                path,
+                size: metadata.len(),
+                last_used,
+            });
+        }
+
+        models.sort_by_key(|m| Reverse(m.last_used));
+        Ok(models)
+    }
+
+    /// Get a cached model by name, if present
+    pub fn get(&self, name: &str) -> Result<Option<CachedModel>> {
+        Ok(self.list()?.into_iter().find(|m| m.name == name))
+    }
+
+    /// Get the total size of the cached models in bytes, including partial downloads
+    pub fn disk_usage(&self) -> Result<u64> {
+        if !self.dir.exists() {
+            return Ok(0);
+        }
+
+        let mut total = 0;
+        for entry in fs::read_dir(&self.dir)? {
+            let entry = entry?;
+            // The usage index is bookkeeping, not something prune can remove
+            if entry.file_name().to_string_lossy().starts_with(INDEX_FILE) {
+                continue;
+            }
+            let metadata = entry.metadata()?;
+            if metadata.is_file() {
+                total += metadata.len();
+            }
+        }
+
+        Ok(total)
+    }
+
+    /// Record that a model was just used
+    pub fn touch(&self, name: &str) -> Result<()> {
+        let file_name = self.file_name(name)?;
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+
+        let mut index = self.read_index();
+        index.insert(file_name, now);
+        self.write_index(&index)
+    }
+
+    /// Check a cached model against its published checksum
+    pub fn verify(&self, model: &CachedModel) -> Result<ChecksumStatus> {
+        match model.model {
+            Some(m) => match verify_model(m, &model.path) {
+                Ok(()) => Ok(ChecksumStatus::Valid),
+                Err(WhisperError::ChecksumMismatch { .. }) => Ok(ChecksumStatus::Mismatch),
+                Err(e) => Err(e),
+            },
+            None => Ok(ChecksumStatus::Unknown),
+        }
+    }
+
+    /// Remove a model from the cache, along with any partial download of it
+    ///
+    /// Returns the removed model, or `None` if it was not in the cache.
+    pub fn remove(&self, name: &str) -> Result<Option<CachedModel>> {
+        let file_name = self.file_name(name)?;
+        let cached = self.get(name)?;
+
+        let path = self.dir.join(&file_name);
+        if path.exists() {
+            fs::remove_file(&path)?;
+        }
+
+        let part = self.dir.join(format!("{}.part", file_name));
+        if part.exists() {
+            fs::remove_file(&part)?;
+        }
+
+        let mut index = self.read_index();
+        if index.remove(&file_name).is_some() {
+            self.write_index(&index)?;
+        }
+
+        Ok(cached)
+    }
+
+    /// Remove the least recently used models until the cache fits in `max_bytes`
+    ///
+    /// The budget covers everything [`disk_usage`](Self::disk_usage) counts,
+    /// partial downloads included. A model's partial download is removed with
+    /// it; if the cache is still too large once every model is gone, the
+    /// remaining partial downloads are removed too.
+    ///
+    /// Returns the models that were removed.
+    pub fn prune(&self, max_bytes: u64) -> Result<Vec<CachedModel>> {
+        let mut models = self.list()?;
+        let mut total = self.disk_usage()?;
+        let mut removed = Vec::new();
+
+        // `list` is most recently used first, so evict from the back
+        while total > max_bytes {
+            let model = match models.pop() {
+                Some(model) => model,
+                None => break,
+            };
+
+            self.remove(&model.name)?;
+            total = self.disk_usage()?;
+            removed.push(model);
+        }
+
+        if total > max_bytes && self.dir.exists() {
+            for entry in fs::read_dir(&self.dir)? {
+                let path = entry?.path();
+                if total <= max_bytes {
+                    break;
+                }
+                if path.is_file() && path.extension() == Some(OsStr::new("part")) {
+                    let size = fs::metadata(&path)?.len();
+                    fs::remove_file(&path)?;
+                    total = total.saturating_sub(size);
+                }
+            }
+        }
+
+        Ok(removed)
+    }
+
+    // Get the file name for a model name, rejecting names that escape the cache
+    fn file_name(&self, name: &str) -> Result<String> {
+        if name.is_empty() || name.contains(['/', '\\']) || name.contains("..") {
+            return Err(WhisperError::Other(format!("Invalid model name: {}", name)));
+        }
+
+        // Files copied in by hand may not follow the ggml- naming
+        let file_name = format!("ggml-{}.bin", name);
+        let plain = format!("{}.bin", name);
+        if !self.dir.join(&file_name).exists() && self.dir.join(&plain).exists() {
+            return Ok(plain);
+        }
+
+        Ok(file_name)
+    }
+
+    fn read_index(&self) -> HashMap<String, u64> {
+        fs::read_to_string(self.dir.join(INDEX_FILE))
+            .ok()
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default()
+    }
+
+    fn write_index(&self, index: &HashMap<String, u64>) -> Result<()> {
+        fs::create_dir_all(&self.dir)?;
+
+        // Write to a temporary file first so a crash can't leave a truncated index
+        let tmp = self.dir.join(format!("{}.tmp", INDEX_FILE));
+        let json = serde_json::to_string_pretty(index)
+            .map_err(|e| WhisperError::Other(format!("Failed to write cache index: {}", e)))?;
+        fs::write(&tmp, json)?;
+        fs::rename(&tmp, self.dir.join(INDEX_FILE))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,84 @@
+//! Fetching the whisper.cpp sources
+//!
+//! Shared by the build script, which includes this file with `#[path]`, and
+//! by [`build_whisper_binary`](crate::commands::build_whisper_binary), so the
+//! linked library and the binary come from the same revision. Only uses std,
+//! as the build script can't use the crate's dependencies.
+
+use std::env;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::process::Command;
+
+/// The whisper.cpp release this crate is built and tested against
+pub const WHISPER_CPP_VERSION: &str = "v1.8.3";
+
+/// Environment variable with the whisper.cpp git tag, branch or commit to build
+/// instead of [`WHISPER_CPP_VERSION`]
+pub const WHISPER_CPP_REF_ENV: &str = "WHISPER_CPP_REF";
+
+/// Environment variable with the whisper.cpp git repository to fetch from,
+/// such as a fork or a local mirror
+pub const WHISPER_CPP_REPO_ENV: &str = "WHISPER_CPP_REPO";
+
+// Upstream whisper.cpp repository
+const WHISPER_CPP_REPO: &str = "https://github.com/ggml-org/whisper.cpp";
+
+// Get the revision requested through WHISPER_CPP_REF, if any
+pub(crate) fn requested_ref() -> Option<String> {
+    env::var(WHISPER_CPP_REF_ENV)
+        .ok()
+        .filter(|r| !r.trim().is_empty())
+}
+
+// Get the repository to fetch from
+pub(crate) fn whisper_cpp_repo() -> String {
+    env::var(WHISPER_CPP_REPO_ENV).unwrap_or_else(|_| WHISPER_CPP_REPO.to_string())
+}
+
+// Check out a single revision of whisper.cpp into `dir`. An existing
+// checkout of another revision is replaced, so the build always matches the
+// requested revision.
+pub(crate) fn checkout_whisper_cpp(dir: &Path, repo: &str, git_ref: &str) -> io::Result<()> {
+    // Record what was checked out inside .git, where whisper.cpp won't see it
+    let marker = dir.join(".git").join("whisper-wrapper-ref");
+    let wanted = format!("{} {}", repo, git_ref);
+    if fs::read_to_string(&marker).ok().as_deref() == Some(wanted.as_str()) {
+        return Ok(());
+    }
+
+    if dir.exists() {
+        fs::remove_dir_all(dir)?;
+    }
+    fs::create_dir_all(dir)?;
+
+    println!("Fetching whisper.cpp {} from {}...", git_ref, repo);
+    let steps: [&[&str]; 3] = [
+        &["init", "-q"],
+        &["fetch", "-q", "--depth", "1", repo, git_ref],
+        &[
+            "-c",
+            "advice.detachedHead=false",
+            "checkout",
+            "-q",
+            "FETCH_HEAD",
+        ],
+    ];
+    for args in steps {
+        let status = Command::new("git")
+            .args(args)
+            .current_dir(dir)
+            .status()
+            .map_err(|e| io::Error::new(e.kind(), format!("Failed to run git: {}", e)))?;
+
+        if !status.success() {
+            return Err(io::Error::other(format!(
+                "Failed to fetch whisper.cpp {} from {}",
+                git_ref, repo
+            )));
+        }
+    }
+
+    fs::write(&marker, wanted)
+}
@@ -1,194 +1,1004 @@
-use anyhow::{Context, Result};
 use std::env;
+use std::ffi::{OsStr, OsString};
 use std::fs;
+use std::io::{BufRead, BufReader};
 use std::path::{Path, PathBuf};
-use std::process::Command;
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
 
-/// Executes the whisper.cpp binary directly with the given arguments
-pub fn execute_whisper_cpp(
-    model_path: &Path,
-    audio_path: &Path,
-    output_path: Option<&Path>,
-    language: Option<&str>,
-    translate: bool,
-    output_format: Option<&str>,
-    additional_args: Vec<String>,
-    binary_path: Option<PathBuf>,
-) -> Result<String> {
-    // Find the whisper.cpp binary
-    let whisper_binary = if let Some(path) = binary_path {
-        path
-    } else {
-        find_whisper_binary()?
-    };
+use crate::checkout;
+use crate::error::WhisperError;
+use crate::probe::BinaryInfo;
+use crate::transcript::{parse_segment_line, Segment, Transcript};
+use crate::Result;
 
-    // Build the command
-    let mut cmd = Command::new(&whisper_binary);
+/// Output formats written by the whisper.cpp binary
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Txt,
+    Vtt,
+    Srt,
+    Lrc,
+    Csv,
+    Json,
+    /// JSON including token-level details
+    JsonFull,
+}
 
-    // Add the model path
-    cmd.arg("-m").arg(model_path);
+impl OutputFormat {
+    /// Get the whisper.cpp flag that enables this format
+    pub fn flag(self) -> &'static str {
+        match self {
+            OutputFormat::Txt => "--output-txt",
+            OutputFormat::Vtt => "--output-vtt",
+            OutputFormat::Srt => "--output-srt",
+            OutputFormat::Lrc => "--output-lrc",
+            OutputFormat::Csv => "--output-csv",
+            OutputFormat::Json => "--output-json",
+            OutputFormat::JsonFull => "--output-json-full",
+        }
+    }
 
-    // Add the audio path
-    cmd.arg(audio_path);
+    /// Get the extension whisper.cpp appends to the output file
+    pub fn extension(self) -> &'static str {
+        match self {
+            OutputFormat::Txt => "txt",
+            OutputFormat::Vtt => "vtt",
+            OutputFormat::Srt => "srt",
+            OutputFormat::Lrc => "lrc",
+            OutputFormat::Csv => "csv",
+            OutputFormat::Json | OutputFormat::JsonFull => "json",
+        }
+    }
+}
 
-    // Add language if specified
-    if let Some(lang) = language {
-        if lang != "auto" {
-            cmd.arg("-l").arg(lang);
+impl OutputFormat {
+    // Flags accepted for this format, preferred first
+    fn spellings(self) -> &'static [&'static str] {
+        match self {
+            OutputFormat::Txt => &["--output-txt", "-otxt"],
+            OutputFormat::Vtt => &["--output-vtt", "-ovtt"],
+            OutputFormat::Srt => &["--output-srt", "-osrt"],
+            OutputFormat::Lrc => &["--output-lrc", "-olrc"],
+            OutputFormat::Csv => &["--output-csv", "-ocsv"],
+            OutputFormat::Json => &["--output-json", "-oj"],
+            OutputFormat::JsonFull => &["--output-json-full", "-ojf", "--output-json", "-oj"],
         }
     }
+}
 
-    // Add translate flag if needed
-    if translate {
-        cmd.arg("--translate");
+impl FromStr for OutputFormat {
+    type Err = WhisperError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "txt" => Ok(OutputFormat::Txt),
+            "vtt" => Ok(OutputFormat::Vtt),
+            "srt" => Ok(OutputFormat::Srt),
+            "lrc" => Ok(OutputFormat::Lrc),
+            "csv" => Ok(OutputFormat::Csv),
+            "json" => Ok(OutputFormat::Json),
+            "json-full" => Ok(OutputFormat::JsonFull),
+            _ => Err(WhisperError::Other(format!(
+                "Unsupported output format: {}",
+                s
+            ))),
+        }
     }
+}
 
-    // Add output format if specified
-    if let Some(format) = output_format {
-        match format {
-            "txt" => cmd.arg("--output-txt"),
-            "srt" => cmd.arg("--output-srt"),
-            "vtt" => cmd.arg("--output-vtt"),
-            "json" => cmd.arg("--output-json"),
-            _ => return Err(anyhow::anyhow!("Unsupported output format: {}", format)),
-        };
+/// An event from a running whisper.cpp process
+#[derive(Debug, Clone, PartialEq)]
+pub enum WhisperEvent {
+    /// A transcribed segment
+    Segment(Segment),
+
+    /// Progress in percent, printed with `--print-progress`
+    Progress(u32),
+
+    /// The automatically detected language
+    Language(String),
+
+    /// Any other line printed to stdout
+    Stdout(String),
+
+    /// Any other line printed to stderr
+    Stderr(String),
+}
+
+/// A handle to cancel a running whisper.cpp process from another thread
+///
+/// Clones share the same state, so cancelling any of them kills the
+/// process of every [`WhisperCommand`] it was passed to.
+#[derive(Debug, Clone, Default)]
+pub struct CancelHandle(Arc<AtomicBool>);
+
+impl CancelHandle {
+    /// Create a new handle
+    pub fn new() -> Self {
+        Self::default()
     }
 
-    // Add output path if specified
-    if let Some(path) = output_path {
-        cmd.arg("-o").arg(path);
+    /// Kill the process
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    /// Check whether the handle has been cancelled
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
     }
+}
+
+// How often the timeout and cancellation are checked
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+// Number of stderr lines kept for the error of a failed run
+const STDERR_TAIL_LINES: usize = 20;
+
+// A line of output from the process
+pub(crate) enum Line {
+    Stdout(String),
+    Stderr(String),
+}
+
+// Send every line read from a pipe, stopping when it closes or the
+// receiver is gone
+fn forward_lines<R: std::io::Read>(pipe: R, tx: Sender<Line>, wrap: fn(String) -> Line) {
+    let mut reader = BufReader::new(pipe);
+    let mut buf = Vec::new();
+
+    loop {
+        buf.clear();
+        match reader.read_until(b'\n', &mut buf) {
+            Ok(0) | Err(_) => break,
+            Ok(_) => {
+                let line = String::from_utf8_lossy(&buf);
+                let line = line.trim_end_matches(['\r', '\n']).to_string();
+                if tx.send(wrap(line)).is_err() {
+                    break;
+                }
+            }
+        }
+    }
+}
 
-    // Add any additional arguments
-    for arg in additional_args {
-        cmd.arg(arg);
+// Spawn a process and hand every output line to `on_line` as it is
+// printed, enforcing the timeout and cancellation
+pub(crate) fn run_process(
+    mut cmd: Command,
+    timeout: Option<Duration>,
+    cancel: Option<&CancelHandle>,
+    on_line: &mut dyn FnMut(Line),
+) -> Result<ExitStatus> {
+    let mut child = cmd
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| WhisperError::CommandError(format!("failed to start: {}", e)))?;
+
+    // Read both pipes on their own threads so neither can fill up and
+    // stall the process
+    let (tx, rx) = mpsc::channel();
+    if let Some(stdout) = child.stdout.take() {
+        let tx = tx.clone();
+        thread::spawn(move || forward_lines(stdout, tx, Line::Stdout));
+    }
+    if let Some(stderr) = child.stderr.take() {
+        thread::spawn(move || forward_lines(stderr, tx, Line::Stderr));
     }
 
-    // Execute the command
-    let output = cmd
-        .output()
-        .with_context(|| format!("Failed to execute whisper.cpp command"))?;
+    let deadline = timeout.map(|t| Instant::now() + t);
+    let mut pipes_open = true;
+
+    loop {
+        if cancel.is_some_and(|c| c.is_cancelled()) {
+            kill(&mut child);
+            return Err(WhisperError::Cancelled);
+        }
+        if deadline.is_some_and(|d| Instant::now() >= d) {
+            kill(&mut child);
+            return Err(WhisperError::Timeout(timeout.unwrap_or_default()));
+        }
 
-    // Check if the command was successful
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(anyhow::anyhow!("whisper.cpp command failed: {}", stderr));
+        if pipes_open {
+            match rx.recv_timeout(POLL_INTERVAL) {
+                Ok(line) => on_line(line),
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => pipes_open = false,
+            }
+        } else {
+            match child.try_wait()? {
+                Some(status) => return Ok(status),
+                None => thread::sleep(POLL_INTERVAL),
+            }
+        }
     }
+}
 
-    // Return the stdout as a string
-    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
-    Ok(stdout)
+// Kill the process and reap it
+fn kill(child: &mut Child) {
+    let _ = child.kill();
+    let _ = child.wait();
 }
 
-/// Finds the path to the whisper.cpp binary
-pub fn find_whisper_binary() -> Result<PathBuf> {
-    // Try common binary names
-    let binary_names = vec!["whisper", "main"];
+// Parse a progress line such as
+// `whisper_print_progress_callback: progress =  40%`
+fn parse_progress_line(line: &str) -> Option<u32> {
+    let (_, rest) = line.split_once("progress =")?;
+    rest.trim().strip_suffix('%')?.trim().parse().ok()
+}
+
+// Parse a language detection line such as
+// `whisper_full_with_state: auto-detected language: en (p = 0.965163)`
+fn parse_language_line(line: &str) -> Option<String> {
+    let (_, rest) = line.split_once("auto-detected language:")?;
+    let language = rest.split_whitespace().next()?;
+    Some(language.to_string())
+}
+
+/// A builder for running the whisper.cpp binary
+///
+/// Covers the commonly used whisper.cpp flags with typed setters; anything
+/// else can be passed through with [`arg`](WhisperCommand::arg).
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::path::Path;
+/// use std::time::Duration;
+/// use whisper_wrapper_rust::commands::{OutputFormat, WhisperCommand};
+///
+/// fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let stdout = WhisperCommand::new(Path::new("ggml-base.bin"), Path::new("audio.wav"))
+///         .language("de")
+///         .threads(8)
+///         .offset(Duration::from_secs(30))
+///         .beam_size(5)
+///         .output_file(Path::new("transcripts/audio"))
+///         .output_format(OutputFormat::Srt)
+///         .arg("--suppress-nst")
+///         .run()?;
+///     println!("{}", stdout);
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct WhisperCommand {
+    /// Path to the whisper.cpp binary (None to search for it)
+    binary: Option<PathBuf>,
+
+    /// Path to the model file
+    model: PathBuf,
+
+    /// Path to the audio file
+    audio: PathBuf,
+
+    /// Spoken language (None for the whisper.cpp default)
+    language: Option<String>,
+
+    /// Whether to translate to English
+    translate: bool,
+
+    /// Number of threads per processor
+    threads: Option<u32>,
+
+    /// Number of processors to split the audio between
+    processors: Option<u32>,
+
+    /// Where to start in the audio
+    offset: Option<Duration>,
+
+    /// How much audio to process
+    duration: Option<Duration>,
+
+    /// Maximum segment length in characters
+    max_len: Option<u32>,
+
+    /// Number of beams for beam search
+    beam_size: Option<u32>,
+
+    /// Number of candidates when sampling
+    best_of: Option<u32>,
+
+    /// Initial prompt
+    prompt: Option<String>,
+
+    /// Word timestamp probability threshold
+    word_thold: Option<f32>,
+
+    /// Output file path, without extension
+    output_file: Option<PathBuf>,
+
+    /// Output files to write
+    output_formats: Vec<OutputFormat>,
+
+    /// Whether to diarize stereo audio
+    diarize: bool,
+
+    /// Whether to leave timestamps out of the printed results
+    no_timestamps: bool,
+
+    /// Additional raw arguments
+    extra_args: Vec<OsString>,
+
+    /// Maximum wall-clock time before the process is killed
+    timeout: Option<Duration>,
+
+    /// Handle to cancel the process from another thread
+    cancel: Option<CancelHandle>,
+
+    /// Whether to check the flags against the binary before running it
+    check_flags: bool,
+}
+
+impl WhisperCommand {
+    /// Create a command transcribing `audio_path` with `model_path`
+    pub fn new(model_path: &Path, audio_path: &Path) -> Self {
+        Self {
+            binary: None,
+            model: model_path.to_path_buf(),
+            audio: audio_path.to_path_buf(),
+            language: None,
+            translate: false,
+            threads: None,
+            processors: None,
+            offset: None,
+            duration: None,
+            max_len: None,
+            beam_size: None,
+            best_of: None,
+            prompt: None,
+            word_thold: None,
+            output_file: None,
+            output_formats: Vec::new(),
+            diarize: false,
+            no_timestamps: false,
+            extra_args: Vec::new(),
+            timeout: None,
+            cancel: None,
+            check_flags: false,
+        }
+    }
+
+    /// Set the path to the whisper.cpp binary instead of searching for it
+    pub fn binary(mut self, path: &Path) -> Self {
+        self.binary = Some(path.to_path_buf());
+        self
+    }
+
+    /// Set the spoken language ("auto" to detect it)
+    pub fn language(mut self, language: &str) -> Self {
+        self.language = Some(language.to_string());
+        self
+    }
+
+    /// Set whether to translate to English
+    pub fn translate(mut self, translate: bool) -> Self {
+        self.translate = translate;
+        self
+    }
+
+    /// Set the number of threads per processor
+    pub fn threads(mut self, threads: u32) -> Self {
+        self.threads = Some(threads);
+        self
+    }
+
+    /// Set the number of processors to split the audio between
+    pub fn processors(mut self, processors: u32) -> Self {
+        self.processors = Some(processors);
+        self
+    }
+
+    /// Set where to start in the audio
+    pub fn offset(mut self, offset: Duration) -> Self {
+        self.offset = Some(offset);
+        self
+    }
+
+    /// Set how much audio to process
+    pub fn duration(mut self, duration: Duration) -> Self {
+        self.duration = Some(duration);
+        self
+    }
+
+    /// Set the maximum segment length in characters
+    pub fn max_len(mut self, max_len: u32) -> Self {
+        self.max_len = Some(max_len);
+        self
+    }
+
+    /// Set the number of beams for beam search
+    pub fn beam_size(mut self, beam_size: u32) -> Self {
+        self.beam_size = Some(beam_size);
+        self
+    }
+
+    /// Set the number of candidates when sampling
+    pub fn best_of(mut self, best_of: u32) -> Self {
+        self.best_of = Some(best_of);
+        self
+    }
+
+    /// Set the initial prompt
+    pub fn prompt(mut self, prompt: &str) -> Self {
+        self.prompt = Some(prompt.to_string());
+        self
+    }
+
+    /// Set the word timestamp probability threshold
+    pub fn word_thold(mut self, word_thold: f32) -> Self {
+        self.word_thold = Some(word_thold);
+        self
+    }
+
+    /// Set the output file path, without extension
+    ///
+    /// whisper.cpp appends the extension of each output format.
+    pub fn output_file(mut self, path: &Path) -> Self {
+        self.output_file = Some(path.to_path_buf());
+        self
+    }
+
+    /// Add an output file to write
+    pub fn output_format(mut self, format: OutputFormat) -> Self {
+        if !self.output_formats.contains(&format) {
+            self.output_formats.push(format);
+        }
+        self
+    }
+
+    /// Set whether to diarize stereo audio
+    pub fn diarize(mut self, diarize: bool) -> Self {
+        self.diarize = diarize;
+        self
+    }
+
+    /// Set whether to leave timestamps out of the printed results
+    pub fn no_timestamps(mut self, no_timestamps: bool) -> Self {
+        self.no_timestamps = no_timestamps;
+        self
+    }
 
-    // Try to find the binary in PATH
-    for name in &binary_names {
-        let which_cmd = if cfg!(target_os = "windows") {
-            "where"
+    /// Add a raw argument, for flags without a typed setter
+    pub fn arg<S: AsRef<OsStr>>(mut self, arg: S) -> Self {
+        self.extra_args.push(arg.as_ref().to_os_string());
+        self
+    }
+
+    /// Add several raw arguments
+    pub fn args<I, S>(mut self, args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        self.extra_args
+            .extend(args.into_iter().map(|a| a.as_ref().to_os_string()));
+        self
+    }
+
+    /// Set the maximum wall-clock time before the process is killed
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Set a handle that kills the process when cancelled
+    pub fn cancel_handle(mut self, cancel: &CancelHandle) -> Self {
+        self.cancel = Some(cancel.clone());
+        self
+    }
+
+    /// Set whether to check the binary supports every typed option before running it
+    ///
+    /// The binary is probed once with [`BinaryInfo::probe`]. Options with
+    /// several spellings use whichever the binary knows, and JSON output
+    /// falls back from `--output-json-full` to `--output-json` on releases
+    /// without it. Raw arguments are passed through unchecked.
+    pub fn check_flags(mut self, check_flags: bool) -> Self {
+        self.check_flags = check_flags;
+        self
+    }
+
+    /// Get the path to the model file
+    pub fn get_model(&self) -> &Path {
+        &self.model
+    }
+
+    /// Get the path to the audio file
+    pub fn get_audio(&self) -> &Path {
+        &self.audio
+    }
+
+    /// Get the output formats
+    pub fn get_output_formats(&self) -> &[OutputFormat] {
+        &self.output_formats
+    }
+
+    /// Get the arguments passed to the binary
+    pub fn to_args(&self) -> Vec<OsString> {
+        // Without a probed binary every flag is accepted
+        self.build_args(None).unwrap_or_default()
+    }
+
+    // Build the arguments, using for each flag the first of its spellings
+    // the binary supports. Without a probe, the first spelling is used.
+    fn build_args(&self, info: Option<&BinaryInfo>) -> Result<Vec<OsString>> {
+        let pick = |names: &[&'static str]| -> Result<&'static str> {
+            match info {
+                None => Ok(names[0]),
+                Some(info) => names
+                    .iter()
+                    .copied()
+                    .find(|name| info.supports(name))
+                    .ok_or_else(|| WhisperError::UnsupportedFlag {
+                        flag: names[0].to_string(),
+                        binary: info.get_path().to_path_buf(),
+                        version: info.get_version().map(|v| v.to_string()),
+                    }),
+            }
+        };
+
+        let mut args: Vec<OsString> = Vec::new();
+        let mut flag = |names: &[&'static str], value: Option<&dyn AsRef<OsStr>>| {
+            args.push(pick(names)?.into());
+            if let Some(value) = value {
+                args.push(value.as_ref().to_os_string());
+            }
+            Ok::<(), WhisperError>(())
+        };
+
+        flag(&["-m", "--model"], Some(&self.model))?;
+        flag(&["-f", "--file"], Some(&self.audio))?;
+
+        if let Some(language) = &self.language {
+            flag(&["-l", "--language"], Some(language))?;
+        }
+        if let Some(threads) = self.threads {
+            flag(&["-t", "--threads"], Some(&threads.to_string()))?;
+        }
+        if let Some(processors) = self.processors {
+            flag(&["-p", "--processors"], Some(&processors.to_string()))?;
+        }
+        if let Some(offset) = self.offset {
+            flag(
+                &["-ot", "--offset-t"],
+                Some(&offset.as_millis().to_string()),
+            )?;
+        }
+        if let Some(duration) = self.duration {
+            flag(
+                &["-d", "--duration"],
+                Some(&duration.as_millis().to_string()),
+            )?;
+        }
+        if let Some(max_len) = self.max_len {
+            flag(&["-ml", "--max-len"], Some(&max_len.to_string()))?;
+        }
+        if let Some(beam_size) = self.beam_size {
+            flag(&["-bs", "--beam-size"], Some(&beam_size.to_string()))?;
+        }
+        if let Some(best_of) = self.best_of {
+            flag(&["-bo", "--best-of"], Some(&best_of.to_string()))?;
+        }
+        if let Some(prompt) = &self.prompt {
+            flag(&["--prompt"], Some(prompt))?;
+        }
+        if let Some(word_thold) = self.word_thold {
+            flag(&["-wt", "--word-thold"], Some(&word_thold.to_string()))?;
+        }
+        if let Some(output_file) = &self.output_file {
+            flag(&["-of", "--output-file"], Some(output_file))?;
+        }
+
+        if self.translate {
+            flag(&["--translate", "-tr"], None)?;
+        }
+        if self.diarize {
+            flag(&["--diarize", "-di"], None)?;
+        }
+        if self.no_timestamps {
+            flag(&["--no-timestamps", "-nt"], None)?;
+        }
+        for format in &self.output_formats {
+            flag(format.spellings(), None)?;
+        }
+
+        args.extend(self.extra_args.iter().cloned());
+        Ok(args)
+    }
+
+    /// Build the process to run, locating the binary if no path was set
+    ///
+    /// With [`check_flags`](WhisperCommand::check_flags), the binary is
+    /// probed first and an error is returned if it doesn't support one of
+    /// the typed options.
+    pub fn to_command(&self) -> Result<Command> {
+        let binary = match &self.binary {
+            Some(path) => path.clone(),
+            None => find_whisper_binary()?,
+        };
+
+        let args = if self.check_flags {
+            self.build_args(Some(&BinaryInfo::probe(&binary)?))?
         } else {
-            "which"
+            self.to_args()
         };
 
-        let output = Command::new(which_cmd).arg(name).output();
+        let mut cmd = Command::new(binary);
+        cmd.args(args);
+        Ok(cmd)
+    }
 
-        if let Ok(output) = output {
-            if output.status.success() {
-                let path = String::from_utf8_lossy(&output.stdout).trim().to_string();
-                return Ok(PathBuf::from(path));
+    /// Run whisper.cpp and return what it printed to stdout
+    pub fn run(&self) -> Result<String> {
+        let mut stdout = String::new();
+        self.execute(&mut |line| {
+            if let Line::Stdout(line) = line {
+                stdout.push_str(&line);
+                stdout.push('\n');
             }
+        })?;
+        Ok(stdout)
+    }
+
+    /// Run whisper.cpp, reporting its output as it is printed
+    ///
+    /// Segments, progress updates (with `--print-progress`) and the detected
+    /// language are parsed into [`WhisperEvent`]s; every other line is passed
+    /// through as is. The segments are also returned as a [`Transcript`] once
+    /// the process exits.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::path::Path;
+    /// use std::time::Duration;
+    /// use whisper_wrapper_rust::commands::{CancelHandle, WhisperCommand, WhisperEvent};
+    ///
+    /// fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let cancel = CancelHandle::new();
+    ///     let transcript = WhisperCommand::new(Path::new("ggml-base.bin"), Path::new("audio.wav"))
+    ///         .arg("--print-progress")
+    ///         .timeout(Duration::from_secs(600))
+    ///         .cancel_handle(&cancel)
+    ///         .stream(|event| match event {
+    ///             WhisperEvent::Segment(segment) => println!("{}", segment.text),
+    ///             WhisperEvent::Progress(percent) => eprintln!("{}%", percent),
+    ///             _ => {}
+    ///         })?;
+    ///     println!("{} segments", transcript.segments.len());
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn stream<F>(&self, mut on_event: F) -> Result<Transcript>
+    where
+        F: FnMut(WhisperEvent),
+    {
+        let mut transcript = Transcript::default();
+
+        self.execute(&mut |line| {
+            let event = match line {
+                Line::Stdout(line) => match parse_segment_line(&line) {
+                    Some(segment) => {
+                        transcript.segments.push(segment.clone());
+                        WhisperEvent::Segment(segment)
+                    }
+                    None => WhisperEvent::Stdout(line),
+                },
+                Line::Stderr(line) => {
+                    if let Some(percent) = parse_progress_line(&line) {
+                        WhisperEvent::Progress(percent)
+                    } else if let Some(language) = parse_language_line(&line) {
+                        transcript.language = Some(language.clone());
+                        WhisperEvent::Language(language)
+                    } else {
+                        WhisperEvent::Stderr(line)
+                    }
+                }
+            };
+            on_event(event);
+        })?;
+
+        Ok(transcript)
+    }
+
+    // Spawn the process and hand every output line to `on_line` as it is
+    // printed, enforcing the timeout and cancellation
+    fn execute(&self, on_line: &mut dyn FnMut(Line)) -> Result<()> {
+        let mut stderr_tail = Vec::new();
+
+        let status = run_process(
+            self.to_command()?,
+            self.timeout,
+            self.cancel.as_ref(),
+            &mut |line| {
+                if let Line::Stderr(text) = &line {
+                    if stderr_tail.len() == STDERR_TAIL_LINES {
+                        stderr_tail.remove(0);
+                    }
+                    stderr_tail.push(text.clone());
+                }
+                on_line(line);
+            },
+        )?;
+
+        if !status.success() {
+            return Err(WhisperError::CommandError(format!(
+                "exited with {}: {}",
+                status,
+                stderr_tail.join("\n").trim()
+            )));
         }
+
+        Ok(())
     }
 
-    // If not found in PATH, try common locations
-    let common_locations = vec![
-        "/usr/local/bin/whisper",
-        "/usr/bin/whisper",
-        "/opt/whisper/bin/whisper",
-        "/usr/local/bin/main",
-        "/usr/bin/main",
-        "/opt/whisper/bin/main",
-    ];
+    /// Run whisper.cpp and read the results from its JSON output
+    ///
+    /// Token details are included. If no output file was set, the JSON is
+    /// written to a temporary directory that is removed afterwards;
+    /// otherwise `<output file>.json` is kept alongside the other outputs.
+    pub fn transcribe(&self) -> Result<Transcript> {
+        // Keep the directory alive until the JSON has been read
+        let temp_dir = match &self.output_file {
+            Some(_) => None,
+            None => Some(tempfile::tempdir()?),
+        };
+        let output_file = match &temp_dir {
+            Some(dir) => dir.path().join("transcript"),
+            None => self.output_file.clone().unwrap_or_default(),
+        };
 
-    for location in &common_locations {
-        let path = PathBuf::from(location);
-        if path.exists() {
+        self.clone()
+            .output_file(&output_file)
+            .output_format(OutputFormat::JsonFull)
+            .run()?;
+
+        let mut json_path = output_file.into_os_string();
+        json_path.push(".json");
+        let json = fs::read(&json_path).map_err(|e| {
+            WhisperError::CommandError(format!(
+                "did not write its JSON output to {:?}: {}",
+                json_path, e
+            ))
+        })?;
+
+        let transcript = Transcript::from_whisper_json(&String::from_utf8_lossy(&json));
+        drop(temp_dir);
+        transcript
+    }
+}
+
+/// Executes the whisper.cpp binary directly with the given arguments
+#[deprecated(note = "use WhisperCommand, which covers more whisper.cpp flags")]
+#[allow(clippy::too_many_arguments)]
+pub fn execute_whisper_cpp(
+    model_path: &Path,
+    audio_path: &Path,
+    output_path: Option<&Path>,
+    language: Option<&str>,
+    translate: bool,
+    output_format: Option<&str>,
+    additional_args: Vec<String>,
+    binary_path: Option<PathBuf>,
+) -> anyhow::Result<String> {
+    let mut cmd = WhisperCommand::new(model_path, audio_path)
+        .translate(translate)
+        .args(additional_args);
+
+    if let Some(path) = binary_path {
+        cmd = cmd.binary(&path);
+    }
+    if let Some(lang) = language {
+        cmd = cmd.language(lang);
+    }
+    if let Some(format) = output_format {
+        cmd = cmd.output_format(format.parse()?);
+    }
+    // whisper.cpp adds the extension itself
+    if let Some(path) = output_path {
+        cmd = cmd.output_file(&path.with_extension(""));
+    }
+
+    Ok(cmd.run()?)
+}
+
+/// Environment variable with the path to the whisper.cpp binary
+pub const WHISPER_CPP_BIN_ENV: &str = "WHISPER_CPP_BIN";
+
+// Names of the transcription binary, newest first: whisper.cpp renamed
+// `main` to `whisper-cli` in 1.7. `whisper` is left out as it is usually
+// openai-whisper's Python script, which takes other arguments.
+const CLI_NAMES: &[&str] = &["whisper-cli", "main"];
+
+// Name of the HTTP server binary
+const SERVER_NAMES: &[&str] = &["whisper-server", "server"];
+
+/// Finds the path to the whisper.cpp binary
+///
+/// Looks in order at:
+///
+/// 1. the `WHISPER_CPP_BIN` environment variable, which must point to an
+///    executable if set
+/// 2. every directory in `PATH`, for `whisper-cli`, then the legacy `main`
+///    name
+/// 3. common install locations such as `/usr/local/bin` and `/opt/homebrew/bin`
+/// 4. the build directories of this crate and of
+///    [`build_whisper_binary`]
+///
+/// If nothing is found, the error lists every location that was tried.
+pub fn find_whisper_binary() -> Result<PathBuf> {
+    if let Some(path) = env::var_os(WHISPER_CPP_BIN_ENV) {
+        let path = PathBuf::from(path);
+        if is_executable(&path) {
             return Ok(path);
         }
+        return Err(WhisperError::BinaryNotFound { tried: vec![path] });
     }
 
-    // Try to find in the build directory
-    if let Ok(out_dir) = env::var("OUT_DIR") {
-        let out_dir = PathBuf::from(out_dir);
-        let whisper_dir = out_dir.join("whisper.cpp");
+    find_binary(CLI_NAMES)
+}
+
+/// Finds the path to the whisper.cpp HTTP server binary (`whisper-server`)
+///
+/// Searches the same locations as [`find_whisper_binary`], except for the
+/// `WHISPER_CPP_BIN` override.
+pub fn find_whisper_server() -> Result<PathBuf> {
+    find_binary(SERVER_NAMES)
+}
+
+// Search PATH, common install locations and build directories for the
+// first executable with one of `names`
+fn find_binary(names: &[&str]) -> Result<PathBuf> {
+    let mut dirs: Vec<PathBuf> = env::var_os("PATH")
+        .map(|path| env::split_paths(&path).collect())
+        .unwrap_or_default();
 
-        let possible_paths = vec![
-            whisper_dir.join("build/bin/main"),
-            whisper_dir.join("build/main"),
-            whisper_dir.join("build/whisper"),
-        ];
+    if cfg!(unix) {
+        dirs.extend(
+            [
+                "/usr/local/bin",
+                "/usr/bin",
+                "/opt/homebrew/bin",
+                "/opt/whisper/bin",
+            ]
+            .iter()
+            .map(PathBuf::from),
+        );
+    }
 
-        for path in possible_paths {
-            if path.exists() {
-                return Ok(path);
+    // whisper.cpp builds its examples into build/bin (or build/bin/Release
+    // with multi-config generators)
+    let mut build_dirs = vec![env::temp_dir().join("whisper_cpp_build/whisper.cpp/build")];
+    if let Some(out_dir) = option_env!("OUT_DIR") {
+        build_dirs.push(Path::new(out_dir).join("whisper-build"));
+    }
+    for build_dir in build_dirs {
+        dirs.push(build_dir.join("bin"));
+        dirs.push(build_dir.join("bin/Release"));
+    }
+
+    let mut tried = Vec::new();
+    for name in names {
+        for dir in &dirs {
+            for candidate in executable_names(name) {
+                let path = dir.join(candidate);
+                if is_executable(&path) {
+                    return Ok(path);
+                }
+                tried.push(path);
             }
         }
     }
 
-    // If we still can't find it, suggest building it or using the build_whisper_binary function
-    Err(anyhow::anyhow!(
-        "Could not find whisper.cpp binary. Please make sure whisper.cpp is built and the binary is in your PATH, \
-        or specify the path to the binary using the --binary option, or use the build_whisper_binary function to build it."
-    ))
+    Err(WhisperError::BinaryNotFound { tried })
+}
+
+// File names an executable can have on this platform
+fn executable_names(name: &str) -> Vec<String> {
+    if cfg!(windows) {
+        let extensions = env::var("PATHEXT").unwrap_or_else(|_| ".EXE;.BAT;.CMD".to_string());
+        extensions
+            .split(';')
+            .filter(|ext| !ext.is_empty())
+            .map(|ext| format!("{}{}", name, ext.to_ascii_lowercase()))
+            .collect()
+    } else {
+        vec![name.to_string()]
+    }
+}
+
+// Whether a path is a file the current user can run
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+
+    fs::metadata(path)
+        .map(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+        .unwrap_or(false)
+}
+
+// Whether a path is a file the current user can run
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+pub use crate::checkout::{WHISPER_CPP_REF_ENV, WHISPER_CPP_REPO_ENV, WHISPER_CPP_VERSION};
+
+/// Get the whisper.cpp revision [`build_whisper_binary`] checks out
+///
+/// This is `WHISPER_CPP_REF` if set, otherwise the revision this crate's
+/// bindings were generated from, so the binary matches the linked library.
+pub fn whisper_cpp_ref() -> String {
+    checkout::requested_ref()
+        .or_else(|| option_env!("WHISPER_CPP_BUILD_REF").map(|r| r.to_string()))
+        .unwrap_or_else(|| WHISPER_CPP_VERSION.to_string())
 }
 
 /// Builds the whisper.cpp binary and returns the path to the built binary
-pub fn build_whisper_binary() -> Result<PathBuf> {
-    // Create a temporary directory for building whisper.cpp
-    let temp_dir = env::temp_dir().join("whisper_cpp_build");
-    if !temp_dir.exists() {
-        fs::create_dir_all(&temp_dir)?;
-    }
-
-    // Clone whisper.cpp if it doesn't exist
-    let whisper_dir = temp_dir.join("whisper.cpp");
-    if !whisper_dir.exists() {
-        println!("Cloning whisper.cpp repository...");
-        let status = Command::new("git")
-            .args(&[
-                "clone",
-                "https://github.com/ggml-org/whisper.cpp",
-                whisper_dir.to_str().unwrap(),
-            ])
+///
+/// Checks out the revision from [`whisper_cpp_ref`] into a temporary
+/// directory and builds `whisper-cli` with CMake, which must be installed
+/// along with git and a C++ compiler. Later calls reuse the build as long as
+/// the revision doesn't change.
+pub fn build_whisper_binary() -> anyhow::Result<PathBuf> {
+    let whisper_dir = env::temp_dir().join("whisper_cpp_build/whisper.cpp");
+    checkout::checkout_whisper_cpp(
+        &whisper_dir,
+        &checkout::whisper_cpp_repo(),
+        &whisper_cpp_ref(),
+    )?;
+
+    println!("Building whisper.cpp...");
+    let steps: [&[&str]; 2] = [
+        &[
+            "-B",
+            "build",
+            "-DCMAKE_BUILD_TYPE=Release",
+            "-DWHISPER_BUILD_TESTS=OFF",
+        ],
+        &[
+            "--build",
+            "build",
+            "--config",
+            "Release",
+            "--target",
+            "whisper-cli",
+            "--parallel",
+        ],
+    ];
+    for args in steps {
+        let status = Command::new("cmake")
+            .args(args)
+            .current_dir(&whisper_dir)
             .status()
-            .expect("Failed to clone whisper.cpp repository");
+            .map_err(|e| anyhow::anyhow!("Failed to run cmake: {}", e))?;
 
         if !status.success() {
-            return Err(anyhow::anyhow!("Failed to clone whisper.cpp repository"));
+            return Err(anyhow::anyhow!("Failed to build whisper.cpp"));
         }
     }
 
-    // Build whisper.cpp
-    println!("Building whisper.cpp...");
-    let status = Command::new("make")
-        .current_dir(&whisper_dir)
-        .status()
-        .expect("Failed to build whisper.cpp");
-
-    if !status.success() {
-        return Err(anyhow::anyhow!("Failed to build whisper.cpp"));
-    }
-
-    // Check if the binary was built successfully
-    let binary_path = whisper_dir.join("main");
-    if !binary_path.exists() {
-        return Err(anyhow::anyhow!(
-            "Failed to find the built whisper.cpp binary"
-        ));
-    }
+    // Multi-config generators such as Visual Studio add the configuration
+    let binary_path = ["build/bin", "build/bin/Release"]
+        .iter()
+        .flat_map(|dir| {
+            let dir = whisper_dir.join(dir);
+            executable_names("whisper-cli")
+                .into_iter()
+                .map(move |name| dir.join(name))
+        })
+        .find(|path| is_executable(path))
+        .ok_or_else(|| anyhow::anyhow!("Failed to find the built whisper.cpp binary"))?;
 
     println!(
         "whisper.cpp binary built successfully at: {:?}",
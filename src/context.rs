@@ -1,21 +1,32 @@
-use std::ffi::CString;
-use std::fs;
+use std::ffi::{c_void, CStr, CString};
+use std::io::Read;
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 
+use crate::audio;
 use crate::bindings;
 use crate::error::WhisperError;
-use crate::params::WhisperParams;
+use crate::params::{ContextParams, WhisperParams};
+use crate::state::WhisperState;
+use crate::transcript::{Segment, Token, TokenId, Transcript};
 use crate::Result;
 
 /// A context for the Whisper model
 pub struct WhisperContext {
     ctx: *mut bindings::whisper_context,
-    model_path: PathBuf,
+    model_path: Option<PathBuf>,
 }
 
 impl WhisperContext {
     /// Create a new whisper context from a model file
     pub fn new(model_path: &Path) -> Result<Self> {
+        Self::with_params(model_path, &ContextParams::default())
+    }
+
+    /// Create a new whisper context from a model file with custom context parameters
+    pub fn with_params(model_path: &Path, params: &ContextParams) -> Result<Self> {
+        params.validate()?;
+
         if !model_path.exists() {
             return Err(WhisperError::ModelNotFound(model_path.to_path_buf()));
         }
@@ -23,7 +34,12 @@ impl WhisperContext {
         let model_path_cstring = CString::new(model_path.to_string_lossy().as_bytes())
             .map_err(|_| WhisperError::InitializationError("Invalid model path".to_string()))?;
 
-        let ctx = unsafe { bindings::whisper_init_from_file(model_path_cstring.as_ptr()) };
+        let ctx = unsafe {
+            bindings::whisper_init_from_file_with_params(
+                model_path_cstring.as_ptr(),
+                params.to_whisper_context_params(),
+            )
+        };
 
         if ctx.is_null() {
             return Err(WhisperError::ModelLoadError {
@@ -34,69 +50,332 @@ impl WhisperContext {
 
         Ok(Self {
             ctx,
-            model_path: model_path.to_path_buf(),
+            model_path: Some(model_path.to_path_buf()),
         })
     }
 
-    /// Get the model path
-    pub fn model_path(&self) -> &Path {
-        &self.model_path
+    /// Create a new whisper context from a model held in memory
+    ///
+    /// The buffer is only read during initialization, so it can be dropped
+    /// as soon as this returns.
+    pub fn from_buffer(buffer: &[u8]) -> Result<Self> {
+        Self::from_buffer_with_params(buffer, &ContextParams::default())
     }
 
-    /// Transcribe an audio file
-    pub fn transcribe(&mut self, audio_path: &Path, _params: &WhisperParams) -> Result<String> {
-        if !audio_path.exists() {
-            return Err(WhisperError::AudioNotFound(audio_path.to_path_buf()));
+    /// Create a new whisper context from a model held in memory with custom context parameters
+    pub fn from_buffer_with_params(buffer: &[u8], params: &ContextParams) -> Result<Self> {
+        params.validate()?;
+
+        if buffer.is_empty() {
+            return Err(WhisperError::InitializationError(
+                "Model buffer is empty".to_string(),
+            ));
         }
 
-        // For simplicity, we'll assume the audio is already in the correct format
-        // In a real implementation, you'd want to handle different audio formats and convert if necessary
-        let _audio_data = fs::read(audio_path).map_err(|e| WhisperError::IoError(e))?;
+        // whisper.cpp takes a mutable pointer but never writes through it
+        let ctx = unsafe {
+            bindings::whisper_init_from_buffer_with_params(
+                buffer.as_ptr() as *mut c_void,
+                buffer.len(),
+                params.to_whisper_context_params(),
+            )
+        };
 
-        // Process the audio data
-        // This is a simplified example - in a real implementation, you'd need to:
-        // 1. Load the audio file (e.g., using a crate like rodio or hound)
-        // 2. Convert it to the format expected by whisper (16kHz mono f32 samples)
-        // 3. Pass it to whisper_full
+        if ctx.is_null() {
+            return Err(WhisperError::InitializationError(
+                "Failed to initialize model from buffer".to_string(),
+            ));
+        }
 
-        // For now, we'll just return a placeholder
-        let result = format!(
-            "Transcription of {:?} using model {:?}",
-            audio_path, self.model_path
-        );
+        Ok(Self {
+            ctx,
+            model_path: None,
+        })
+    }
 
-        // In a real implementation, you'd do something like:
-        /*
-        let whisper_params = params.to_whisper_params(self.ctx);
+    /// Create a new whisper context by streaming the model from a reader
+    ///
+    /// This avoids holding the whole model in memory twice, which matters for
+    /// the large models when they come from object storage or an archive.
+    pub fn from_reader<R: Read>(reader: R) -> Result<Self> {
+        Self::from_reader_with_params(reader, &ContextParams::default())
+    }
+
+    /// Create a new whisper context by streaming the model from a reader with custom context parameters
+    pub fn from_reader_with_params<R: Read>(reader: R, params: &ContextParams) -> Result<Self> {
+        params.validate()?;
+
+        let mut source = ReaderSource {
+            reader,
+            eof: false,
+            error: None,
+        };
+
+        let mut loader = bindings::whisper_model_loader {
+            context: &mut source as *mut ReaderSource<R> as *mut c_void,
+            read: Some(reader_read::<R>),
+            eof: Some(reader_eof::<R>),
+            close: Some(reader_close),
+        };
+
+        let ctx = unsafe {
+            bindings::whisper_init_with_params(&mut loader, params.to_whisper_context_params())
+        };
+
+        if let Some(err) = source.error {
+            if !ctx.is_null() {
+                unsafe { bindings::whisper_free(ctx) };
+            }
+            return Err(WhisperError::IoError(err));
+        }
+
+        if ctx.is_null() {
+            return Err(WhisperError::InitializationError(
+                "Failed to initialize model from reader".to_string(),
+            ));
+        }
+
+        Ok(Self {
+            ctx,
+            model_path: None,
+        })
+    }
+
+    /// Get the model path, if the context was created from a file
+    pub fn model_path(&self) -> Option<&Path> {
+        self.model_path.as_deref()
+    }
+
+    /// Create a state to run the model step by step
+    ///
+    /// Each state holds its own buffers, so several can share a context.
+    pub fn create_state(&self) -> Result<WhisperState<'_>> {
+        WhisperState::new(self)
+    }
+
+    /// Get the number of mel bands the model expects
+    pub fn n_mels(&self) -> usize {
+        unsafe { bindings::whisper_model_n_mels(self.ctx) }.max(0) as usize
+    }
+
+    /// Get the maximum number of tokens the decoder can attend to
+    pub fn n_text_ctx(&self) -> usize {
+        unsafe { bindings::whisper_n_text_ctx(self.ctx) }.max(0) as usize
+    }
+
+    pub(crate) fn as_ptr(&self) -> *mut bindings::whisper_context {
+        self.ctx
+    }
+
+    /// Transcribe an audio file
+    ///
+    /// The audio must be a 16 kHz WAV file, see [`read_wav`](crate::read_wav).
+    pub fn transcribe(&mut self, audio_path: &Path, params: &WhisperParams) -> Result<Transcript> {
+        let samples = audio::read_wav(audio_path)?;
+        self.full(&samples, params)
+    }
+
+    /// Transcribe 16 kHz mono samples in the [-1, 1] range
+    pub fn full(&mut self, samples: &[f32], params: &WhisperParams) -> Result<Transcript> {
+        let language = CString::new(params.get_language())
+            .map_err(|_| WhisperError::TranscriptionError("Invalid language".to_string()))?;
+        let whisper_params = params.to_whisper_params(&language);
 
         let status = unsafe {
             bindings::whisper_full(
                 self.ctx,
                 whisper_params,
-                audio_samples.as_ptr(),
-                audio_samples.len() as i32,
+                samples.as_ptr(),
+                samples.len() as i32,
             )
         };
 
         if status != 0 {
-            return Err(WhisperError::TranscriptionError(format!("whisper_full returned {}", status)));
+            return Err(WhisperError::TranscriptionError(format!(
+                "whisper_full returned {}",
+                status
+            )));
         }
 
-        // Extract the text from the segments
+        // Timestamps are in units of 10 ms
+        let timestamp = |t: i64| Duration::from_millis(t.max(0) as u64 * 10);
+        let text = |ptr: *const std::os::raw::c_char| {
+            if ptr.is_null() {
+                String::new()
+            } else {
+                unsafe { CStr::from_ptr(ptr).to_string_lossy().into_owned() }
+            }
+        };
+
         let n_segments = unsafe { bindings::whisper_full_n_segments(self.ctx) };
-        let mut result = String::new();
-
-        for i in 0..n_segments {
-            let text = unsafe {
-                let text_ptr = bindings::whisper_full_get_segment_text(self.ctx, i);
-                CStr::from_ptr(text_ptr).to_string_lossy().into_owned()
-            };
-            result.push_str(&text);
-            result.push('\n');
+        let segments = (0..n_segments)
+            .map(|i| unsafe {
+                let n_tokens = bindings::whisper_full_n_tokens(self.ctx, i);
+                let tokens = (0..n_tokens)
+                    .map(|j| {
+                        let data = bindings::whisper_full_get_token_data(self.ctx, i, j);
+                        Token {
+                            text: text(bindings::whisper_full_get_token_text(self.ctx, i, j)),
+                            id: data.id,
+                            p: data.p,
+                            start: timestamp(data.t0),
+                            end: timestamp(data.t1),
+                            dtw: (data.t_dtw >= 0).then(|| timestamp(data.t_dtw)),
+                        }
+                    })
+                    .collect();
+
+                Segment {
+                    start: timestamp(bindings::whisper_full_get_segment_t0(self.ctx, i)),
+                    end: timestamp(bindings::whisper_full_get_segment_t1(self.ctx, i)),
+                    text: text(bindings::whisper_full_get_segment_text(self.ctx, i)),
+                    tokens,
+                }
+            })
+            .collect();
+
+        let language = unsafe {
+            let lang_ptr = bindings::whisper_lang_str(bindings::whisper_full_lang_id(self.ctx));
+            if lang_ptr.is_null() {
+                None
+            } else {
+                Some(CStr::from_ptr(lang_ptr).to_string_lossy().into_owned())
+            }
+        };
+
+        Ok(Transcript { language, segments })
+    }
+
+    /// Split text into tokens with the model's tokenizer
+    ///
+    /// Useful to build prompts from token ids or to check a prompt against
+    /// the model's context size.
+    pub fn tokenize(&self, text: &str) -> Result<Vec<TokenId>> {
+        let text_cstring = CString::new(text)
+            .map_err(|_| WhisperError::Other("Text contains a NUL byte".to_string()))?;
+
+        // Every token covers at least one byte, so this is always enough
+        let mut tokens: Vec<TokenId> = vec![0; text.len() + 1];
+        let n = unsafe {
+            bindings::whisper_tokenize(
+                self.ctx,
+                text_cstring.as_ptr(),
+                tokens.as_mut_ptr(),
+                tokens.len() as i32,
+            )
+        };
+
+        if n < 0 {
+            return Err(WhisperError::Other(format!(
+                "Failed to tokenize text into {} tokens",
+                tokens.len()
+            )));
         }
-        */
 
-        Ok(result)
+        tokens.truncate(n as usize);
+        Ok(tokens)
+    }
+
+    /// Count the tokens in a text
+    pub fn token_count(&self, text: &str) -> Result<usize> {
+        Ok(self.tokenize(text)?.len())
+    }
+
+    /// Get the number of tokens in the model vocabulary
+    pub fn n_vocab(&self) -> usize {
+        unsafe { bindings::whisper_n_vocab(self.ctx) }.max(0) as usize
+    }
+
+    /// Get the raw bytes of a token
+    ///
+    /// Tokens can hold part of a multi-byte character, so concatenate the
+    /// bytes of a token sequence before decoding it.
+    pub fn token_to_bytes(&self, token: TokenId) -> Result<Vec<u8>> {
+        // whisper.cpp aborts on ids outside the vocabulary
+        if token < 0 || token as usize >= self.n_vocab() {
+            return Err(WhisperError::InvalidToken(token));
+        }
+
+        let ptr = unsafe { bindings::whisper_token_to_str(self.ctx, token) };
+        if ptr.is_null() {
+            return Err(WhisperError::InvalidToken(token));
+        }
+        Ok(unsafe { CStr::from_ptr(ptr) }.to_bytes().to_vec())
+    }
+
+    /// Get the text of a token, replacing incomplete characters
+    pub fn token_to_str(&self, token: TokenId) -> Result<String> {
+        Ok(String::from_utf8_lossy(&self.token_to_bytes(token)?).into_owned())
+    }
+
+    /// Get the end of transcript token
+    pub fn token_eot(&self) -> TokenId {
+        unsafe { bindings::whisper_token_eot(self.ctx) }
+    }
+
+    /// Get the start of transcript token
+    pub fn token_sot(&self) -> TokenId {
+        unsafe { bindings::whisper_token_sot(self.ctx) }
+    }
+
+    /// Get the start of language model token
+    pub fn token_solm(&self) -> TokenId {
+        unsafe { bindings::whisper_token_solm(self.ctx) }
+    }
+
+    /// Get the token that introduces the previous text used as a prompt
+    pub fn token_prev(&self) -> TokenId {
+        unsafe { bindings::whisper_token_prev(self.ctx) }
+    }
+
+    /// Get the no speech token
+    pub fn token_nosp(&self) -> TokenId {
+        unsafe { bindings::whisper_token_nosp(self.ctx) }
+    }
+
+    /// Get the no timestamps token
+    pub fn token_not(&self) -> TokenId {
+        unsafe { bindings::whisper_token_not(self.ctx) }
+    }
+
+    /// Get the first timestamp token, for 0.00 s
+    ///
+    /// Every token from this one up is a timestamp, in steps of 20 ms.
+    pub fn token_beg(&self) -> TokenId {
+        unsafe { bindings::whisper_token_beg(self.ctx) }
+    }
+
+    /// Get the translate task token
+    pub fn token_translate(&self) -> TokenId {
+        unsafe { bindings::whisper_token_translate(self.ctx) }
+    }
+
+    /// Get the transcribe task token
+    pub fn token_transcribe(&self) -> TokenId {
+        unsafe { bindings::whisper_token_transcribe(self.ctx) }
+    }
+
+    /// Get the token of a language, such as `"en"` or `"german"`
+    pub fn token_lang(&self, language: &str) -> Result<TokenId> {
+        let language_cstring = CString::new(language)
+            .map_err(|_| WhisperError::UnknownLanguage(language.to_string()))?;
+
+        let lang_id = unsafe { bindings::whisper_lang_id(language_cstring.as_ptr()) };
+        if lang_id < 0 {
+            return Err(WhisperError::UnknownLanguage(language.to_string()));
+        }
+        Ok(unsafe { bindings::whisper_token_lang(self.ctx, lang_id) })
+    }
+
+    /// Check whether a token is a timestamp
+    pub fn is_timestamp(&self, token: TokenId) -> bool {
+        token >= self.token_beg()
+    }
+
+    /// Check whether a token is special, such as a timestamp, language or
+    /// task token, rather than text
+    pub fn is_special(&self, token: TokenId) -> bool {
+        token >= self.token_eot()
     }
 }
 
@@ -110,6 +389,51 @@ impl Drop for WhisperContext {
     }
 }
 
+/// State handed to whisper.cpp through `whisper_model_loader::context`
+struct ReaderSource<R> {
+    reader: R,
+    eof: bool,
+    error: Option<std::io::Error>,
+}
+
+unsafe extern "C" fn reader_read<R: Read>(
+    ctx: *mut c_void,
+    output: *mut c_void,
+    read_size: usize,
+) -> usize {
+    let source = &mut *(ctx as *mut ReaderSource<R>);
+    let buf = std::slice::from_raw_parts_mut(output as *mut u8, read_size);
+
+    // whisper.cpp expects every read to be filled completely, so keep reading
+    // until the buffer is full or the reader runs dry
+    let mut filled = 0;
+    while filled < read_size {
+        match source.reader.read(&mut buf[filled..]) {
+            Ok(0) => {
+                source.eof = true;
+                break;
+            }
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(e) => {
+                source.eof = true;
+                source.error = Some(e);
+                break;
+            }
+        }
+    }
+
+    filled
+}
+
+unsafe extern "C" fn reader_eof<R: Read>(ctx: *mut c_void) -> bool {
+    (*(ctx as *mut ReaderSource<R>)).eof
+}
+
+unsafe extern "C" fn reader_close(_ctx: *mut c_void) {
+    // The reader is owned by `from_reader` and dropped when it returns
+}
+
 // Ensure the context is Send and Sync
 unsafe impl Send for WhisperContext {}
 unsafe impl Sync for WhisperContext {}
@@ -1,75 +1,599 @@
-use anyhow::{anyhow, Result};
-use dirs::home_dir;
+use std::env;
 use std::fs::{self, File};
-use std::io::Write;
+use std::io::{BufWriter, Read, Write};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, RwLock};
+use std::thread;
+use std::time::Duration;
+
+use crate::cache::{is_offline, sha1_file, ModelCache};
+use crate::error::WhisperError;
+use crate::model::Model;
+use crate::Result;
 
 #[cfg(feature = "indicatif")]
-use indicatif::{ProgressBar, ProgressStyle};
-
-// Model URLs
-const MODEL_URLS: &[(&str, &str)] = &[
-    (
-        "tiny",
-        "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-tiny.bin",
-    ),
-    (
-        "base",
-        "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-base.bin",
-    ),
-    (
-        "small",
-        "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-small.bin",
-    ),
-    (
-        "medium",
-        "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-medium.bin",
-    ),
-    (
-        "large",
-        "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-large.bin",
-    ),
-];
-
-// Get the models directory
-fn get_models_dir() -> PathBuf {
-    let mut models_dir = home_dir().unwrap_or_else(|| PathBuf::from("."));
-    models_dir.push(".whisper-models");
-
-    if !models_dir.exists() {
-        fs::create_dir_all(&models_dir).expect("Failed to create models directory");
-    }
-
-    models_dir
-}
-
-// Download a file with progress bar if indicatif is enabled
-fn download_file(url: &str, path: &Path) -> Result<()> {
-    println!("Downloading from {}", url);
-
-    let client = reqwest::blocking::Client::new();
-    let resp = client.get(url).send()?;
-    let total_size = resp.content_length().unwrap_or(0);
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+
+/// Environment variable with a comma-separated list of mirror base URLs
+pub const MODELS_MIRROR_ENV: &str = "WHISPER_MODELS_MIRROR";
+
+// Mirrors registered through `add_mirror`
+static MIRRORS: RwLock<Vec<String>> = RwLock::new(Vec::new());
+
+/// Register a mirror to download models from
+///
+/// A mirror is a base URL serving the same `ggml-<name>.bin` files as the
+/// whisper.cpp HuggingFace repository, such as an internal Artifactory
+/// remote. Mirrors are tried in the order they were added, followed by the
+/// ones in `WHISPER_MODELS_MIRROR`, and HuggingFace is only used as a last
+/// resort.
+pub fn add_mirror(base_url: &str) {
+    let mut mirrors = MIRRORS.write().unwrap_or_else(|e| e.into_inner());
+    mirrors.push(base_url.trim_end_matches('/').to_string());
+}
+
+/// Remove all mirrors registered through [`add_mirror`]
+pub fn clear_mirrors() {
+    MIRRORS.write().unwrap_or_else(|e| e.into_inner()).clear();
+}
+
+// Get every URL a model can be downloaded from, in the order to try them
+fn model_urls(model: Model) -> Vec<String> {
+    let mut bases = MIRRORS.read().unwrap_or_else(|e| e.into_inner()).clone();
+
+    if let Ok(env_mirrors) = env::var(MODELS_MIRROR_ENV) {
+        bases.extend(
+            env_mirrors
+                .split(',')
+                .map(|m| m.trim().trim_end_matches('/').to_string())
+                .filter(|m| !m.is_empty()),
+        );
+    }
+
+    let mut urls: Vec<String> = bases
+        .iter()
+        .map(|base| format!("{}/{}", base, model.filename()))
+        .collect();
+    urls.push(model.url());
+    urls
+}
+
+// The sources of a model, tried in order until one gives a file matching the
+// model's checksum. Shared by the blocking and async downloads, which only
+// differ in how they fetch each URL.
+pub(crate) struct ModelSources {
+    model: Model,
+    urls: std::vec::IntoIter<String>,
+    last_err: Option<WhisperError>,
+}
+
+impl ModelSources {
+    pub(crate) fn new(model: Model) -> Self {
+        Self {
+            model,
+            urls: model_urls(model).into_iter(),
+            last_err: None,
+        }
+    }
+
+    // Get the next URL to try
+    pub(crate) fn next_url(&mut self) -> Option<String> {
+        self.urls.next()
+    }
+
+    // Get the checksum every source has to match
+    pub(crate) fn sha1(&self) -> &'static str {
+        self.model.sha1()
+    }
+
+    // Record a failed source before moving on to the next one
+    pub(crate) fn failed(&mut self, url: &str, err: WhisperError) {
+        log::warn!("Failed to download {} from {}: {}", self.model, url, err);
+        self.last_err = Some(err);
+    }
+
+    // Get the error to return once every source has failed
+    pub(crate) fn into_error(self) -> WhisperError {
+        self.last_err.unwrap_or_else(|| {
+            WhisperError::DownloadError(format!("No source available for model {}", self.model))
+        })
+    }
+}
+
+/// Environment variable with the HuggingFace access token for private repositories
+pub const HF_TOKEN_ENV: &str = "HF_TOKEN";
+
+// Upper bound for the delay between retries
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Network settings for model downloads
+///
+/// By default, downloads give up connecting after 30 seconds and on a
+/// stalled transfer after 60 seconds, are retried 3 times with exponential
+/// backoff, use the system proxy settings (`HTTPS_PROXY` etc.) and send the
+/// `HF_TOKEN` environment variable as a bearer token to huggingface.co.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::time::Duration;
+/// use whisper_wrapper_rust::{download_model_with_config, DownloadConfig, Model};
+///
+/// fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let config = DownloadConfig::new()
+///         .read_timeout(Duration::from_secs(120))
+///         .retries(5)
+///         .proxy("http://proxy.internal:3128");
+///
+///     let model_path = download_model_with_config(Model::Base, &config)?;
+///     println!("Model downloaded to {:?}", model_path);
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct DownloadConfig {
+    /// Maximum time to establish a connection
+    connect_timeout: Duration,
+
+    /// Maximum time to wait for data before giving up on a transfer
+    read_timeout: Duration,
+
+    /// Number of times a failed download is retried
+    retries: u32,
+
+    /// Delay before the first retry, doubled on every further attempt
+    backoff: Duration,
+
+    /// Proxy URL for all requests
+    proxy: Option<String>,
+
+    /// Bearer token for all requests
+    token: Option<String>,
+
+    /// The User-Agent header
+    user_agent: String,
+
+    /// Maximum number of models fetched at once by `download_models`
+    parallel_downloads: usize,
+}
+
+impl Default for DownloadConfig {
+    fn default() -> Self {
+        Self {
+            connect_timeout: Duration::from_secs(30),
+            read_timeout: Duration::from_secs(60),
+            retries: 3,
+            backoff: Duration::from_secs(1),
+            proxy: None,
+            token: None,
+            user_agent: format!("whisper_wrapper_rust/{}", env!("CARGO_PKG_VERSION")),
+            parallel_downloads: 3,
+        }
+    }
+}
+
+impl DownloadConfig {
+    /// Create a new download configuration with default values
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the maximum time to establish a connection
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = timeout;
+        self
+    }
+
+    /// Set the maximum time to wait for data before giving up on a transfer
+    pub fn read_timeout(mut self, timeout: Duration) -> Self {
+        self.read_timeout = timeout;
+        self
+    }
+
+    /// Set the number of times a failed download is retried
+    pub fn retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// Set the delay before the first retry
+    pub fn backoff(mut self, backoff: Duration) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Set a proxy URL for all requests, overriding the system settings
+    pub fn proxy(mut self, proxy: &str) -> Self {
+        self.proxy = Some(proxy.to_string());
+        self
+    }
+
+    /// Set a bearer token sent with every request, including to mirrors
+    pub fn bearer_token(mut self, token: &str) -> Self {
+        self.token = Some(token.to_string());
+        self
+    }
+
+    /// Set the User-Agent header
+    pub fn user_agent(mut self, user_agent: &str) -> Self {
+        self.user_agent = user_agent.to_string();
+        self
+    }
+
+    /// Set the maximum number of models fetched at once by `download_models`
+    pub fn parallel_downloads(mut self, parallel_downloads: usize) -> Self {
+        self.parallel_downloads = parallel_downloads.max(1);
+        self
+    }
+
+    /// Get the connect timeout
+    pub fn get_connect_timeout(&self) -> Duration {
+        self.connect_timeout
+    }
+
+    /// Get the read timeout
+    pub fn get_read_timeout(&self) -> Duration {
+        self.read_timeout
+    }
+
+    /// Get the number of retries
+    pub fn get_retries(&self) -> u32 {
+        self.retries
+    }
+
+    /// Get the initial retry delay
+    pub fn get_backoff(&self) -> Duration {
+        self.backoff
+    }
+
+    /// Get the proxy URL
+    pub fn get_proxy(&self) -> Option<&str> {
+        self.proxy.as_deref()
+    }
+
+    /// Get the User-Agent header
+    pub fn get_user_agent(&self) -> &str {
+        &self.user_agent
+    }
+
+    /// Get the maximum number of parallel model downloads
+    pub fn get_parallel_downloads(&self) -> usize {
+        self.parallel_downloads
+    }
+
+    // Get the delay before retry number `attempt` (starting at 1)
+    pub(crate) fn backoff_for(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        self.backoff.saturating_mul(factor).min(MAX_BACKOFF)
+    }
+
+    // Get the bearer token to send to a URL. `HF_TOKEN` is only ever sent to
+    // HuggingFace so it doesn't leak to mirrors.
+    pub(crate) fn token_for(&self, url: &str) -> Option<String> {
+        if let Some(token) = &self.token {
+            return Some(token.clone());
+        }
+
+        let host = reqwest::Url::parse(url).ok()?.host_str()?.to_string();
+        if host == "huggingface.co" || host.ends_with(".huggingface.co") {
+            return env::var(HF_TOKEN_ENV).ok().filter(|t| !t.is_empty());
+        }
+
+        None
+    }
+
+    // Build a blocking HTTP client with these settings
+    pub(crate) fn blocking_client(&self) -> Result<reqwest::blocking::Client> {
+        let mut builder = reqwest::blocking::Client::builder()
+            .connect_timeout(self.connect_timeout)
+            .timeout(self.read_timeout)
+            .user_agent(self.user_agent.as_str());
+
+        if let Some(proxy) = &self.proxy {
+            builder = builder.proxy(reqwest::Proxy::all(proxy)?);
+        }
+
+        Ok(builder.build()?)
+    }
+
+    // Build an async HTTP client with these settings. The read timeout is
+    // enforced on the response headers and on each chunk by the caller, as
+    // reqwest's async timeout covers the whole transfer.
+    #[cfg(feature = "async-download")]
+    pub(crate) fn async_client(&self) -> Result<reqwest::Client> {
+        let mut builder = reqwest::Client::builder()
+            .connect_timeout(self.connect_timeout)
+            .user_agent(self.user_agent.as_str());
+
+        if let Some(proxy) = &self.proxy {
+            builder = builder.proxy(reqwest::Proxy::all(proxy)?);
+        }
+
+        Ok(builder.build()?)
+    }
+}
+
+// Turn an unsuccessful HTTP status into an error
+pub(crate) fn check_status(url: &str, status: reqwest::StatusCode) -> Result<()> {
+    if status.is_client_error() || status.is_server_error() {
+        return Err(WhisperError::HttpError {
+            url: url.to_string(),
+            status: status.as_u16(),
+        });
+    }
+
+    Ok(())
+}
+
+// Whether a failed download is worth retrying. Network errors, timeouts and
+// server-side failures are; missing files, auth failures and corrupt data
+// are not.
+pub(crate) fn is_retryable(err: &WhisperError) -> bool {
+    match err {
+        WhisperError::DownloadError(_) => true,
+        WhisperError::HttpError { status, .. } => {
+            *status == 408 || *status == 429 || *status >= 500
+        }
+        _ => false,
+    }
+}
+
+// Size of the chunks streamed from the response to disk
+pub(crate) const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Progress of a download, reported after every chunk written to disk
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DownloadProgress {
+    /// Bytes written so far
+    pub downloaded: u64,
+
+    /// Total size of the download, if the server reported it
+    pub total: Option<u64>,
+}
+
+// Get the path of the partial download for a destination path
+pub(crate) fn part_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".part");
+    path.with_file_name(name)
+}
+
+// Parse the first byte position from a `Content-Range: bytes start-end/total` header
+fn content_range_start(headers: &reqwest::header::HeaderMap) -> Option<u64> {
+    let value = headers.get(reqwest::header::CONTENT_RANGE)?.to_str().ok()?;
+    let range = value.strip_prefix("bytes ")?;
+    range.split('-').next()?.trim().parse().ok()
+}
+
+// Whether a response continues a partial download of `existing` bytes. If
+// not, the server is sending the whole file again.
+pub(crate) fn is_resumed(
+    status: reqwest::StatusCode,
+    headers: &reqwest::header::HeaderMap,
+    existing: u64,
+) -> bool {
+    existing > 0
+        && status == reqwest::StatusCode::PARTIAL_CONTENT
+        && content_range_start(headers) == Some(existing)
+}
 
+// Check a completed partial download and move it into place. A corrupt
+// download can't be resumed, so it is removed to start over next time.
+pub(crate) fn finish_download(
+    url: &str,
+    path: &Path,
+    downloaded: u64,
+    total: Option<u64>,
+    sha1: Option<&str>,
+) -> Result<()> {
+    let part_path = part_path(path);
+
+    if let Some(total) = total {
+        if downloaded != total {
+            return Err(WhisperError::DownloadError(format!(
+                "Download of {} ended after {} of {} bytes",
+                url, downloaded, total
+            )));
+        }
+    }
+
+    if let Some(expected) = sha1 {
+        let actual = sha1_file(&part_path)?;
+        if actual != expected {
+            fs::remove_file(&part_path)?;
+            return Err(WhisperError::ChecksumMismatch {
+                path: path.to_path_buf(),
+                expected: expected.to_string(),
+                actual,
+            });
+        }
+    }
+
+    fs::rename(&part_path, path)?;
+    Ok(())
+}
+
+/// Download a URL to a file, resuming a previous partial download if present
+///
+/// Data is streamed to `<path>.part` and only renamed to `path` once the
+/// transfer is complete, so `path` never refers to a truncated file. If the
+/// transfer is interrupted, the `.part` file is kept and the next call
+/// continues from where it stopped using an HTTP Range request. Failed
+/// transfers are retried according to `config`.
+///
+/// Returns `WhisperError::Offline` without touching the network in offline
+/// mode, see [`set_offline`](crate::set_offline).
+pub fn download_url<F>(
+    url: &str,
+    path: &Path,
+    config: &DownloadConfig,
+    mut progress: F,
+) -> Result<()>
+where
+    F: FnMut(DownloadProgress),
+{
+    if is_offline() {
+        return Err(WhisperError::Offline(url.to_string()));
+    }
+
+    let client = config.blocking_client()?;
+    download_with_retries(&client, config, url, path, None, &mut progress)
+}
+
+// Download a file, retrying with exponential backoff. Every retry resumes
+// from the partial file left by the previous attempt.
+fn download_with_retries(
+    client: &reqwest::blocking::Client,
+    config: &DownloadConfig,
+    url: &str,
+    path: &Path,
+    sha1: Option<&str>,
+    progress: &mut dyn FnMut(DownloadProgress),
+) -> Result<()> {
+    let mut attempt = 0;
+
+    loop {
+        match download_file(client, config, url, path, sha1, progress) {
+            Err(e) if attempt < config.retries && is_retryable(&e) => {
+                attempt += 1;
+                let delay = config.backoff_for(attempt);
+                log::warn!(
+                    "Download of {} failed ({}), retrying in {:?} ({}/{})",
+                    url,
+                    e,
+                    delay,
+                    attempt,
+                    config.retries
+                );
+                thread::sleep(delay);
+            }
+            result => return result,
+        }
+    }
+}
+
+// Stream a file to disk, reporting progress after each chunk. If a digest is
+// given, the completed file is verified before it is moved into place.
+fn download_file(
+    client: &reqwest::blocking::Client,
+    config: &DownloadConfig,
+    url: &str,
+    path: &Path,
+    sha1: Option<&str>,
+    progress: &mut dyn FnMut(DownloadProgress),
+) -> Result<()> {
+    let part_path = part_path(path);
+    let existing = fs::metadata(&part_path).map(|m| m.len()).unwrap_or(0);
+
+    let mut request = client.get(url);
+    if existing > 0 {
+        request = request.header(reqwest::header::RANGE, format!("bytes={}-", existing));
+    }
+    if let Some(token) = config.token_for(url) {
+        request = request.bearer_auth(token);
+    }
+
+    let mut resp = request.send()?;
+
+    // The partial file is already complete or larger than the remote file,
+    // which can only be fixed by starting over
+    if resp.status() == reqwest::StatusCode::RANGE_NOT_SATISFIABLE {
+        fs::remove_file(&part_path)?;
+        return download_file(client, config, url, path, sha1, progress);
+    }
+
+    check_status(url, resp.status())?;
+
+    let resume = is_resumed(resp.status(), resp.headers(), existing);
+
+    let mut downloaded = if resume { existing } else { 0 };
+    let total = resp.content_length().map(|len| len + downloaded);
+
+    let file = if resume {
+        fs::OpenOptions::new().append(true).open(&part_path)?
+    } else {
+        File::create(&part_path)?
+    };
+    let mut file = BufWriter::new(file);
+    let mut buf = vec![0u8; CHUNK_SIZE];
+
+    progress(DownloadProgress { downloaded, total });
+
+    loop {
+        let n = match resp.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => {
+                file.flush()?;
+                return Err(WhisperError::DownloadError(format!(
+                    "Download of {} interrupted after {} bytes: {}",
+                    url, downloaded, e
+                )));
+            }
+        };
+
+        file.write_all(&buf[..n])?;
+        downloaded += n as u64;
+        progress(DownloadProgress { downloaded, total });
+    }
+
+    file.flush()?;
+    file.get_ref().sync_all()?;
+    drop(file);
+
+    finish_download(url, path, downloaded, total, sha1)
+}
+
+// Try every source for a model until one succeeds
+fn download_from_sources(
+    model: Model,
+    path: &Path,
+    config: &DownloadConfig,
+    progress: &mut dyn FnMut(DownloadProgress),
+) -> Result<()> {
+    let client = config.blocking_client()?;
+    let mut sources = ModelSources::new(model);
+
+    while let Some(url) = sources.next_url() {
+        match download_with_retries(&client, config, &url, path, Some(sources.sha1()), progress) {
+            Ok(()) => return Ok(()),
+            Err(e) => sources.failed(&url, e),
+        }
+    }
+
+    Err(sources.into_error())
+}
+
+// Run a download, drawing a progress bar if indicatif is enabled
+fn with_progress_bar<F>(download: F) -> Result<()>
+where
+    F: FnOnce(&mut dyn FnMut(DownloadProgress)) -> Result<()>,
+{
     #[cfg(feature = "indicatif")]
-    let pb = {
-        let pb = ProgressBar::new(total_size);
+    {
+        let pb = ProgressBar::new(0);
         pb.set_style(ProgressStyle::default_bar()
             .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {bytes}/{total_bytes} ({eta})")
             .unwrap()
             .progress_chars("#>-"));
-        pb
-    };
 
-    let mut file = File::create(path)?;
-    let content = resp.bytes()?;
-    file.write_all(&content)?;
+        download(&mut |p| {
+            if let Some(total) = p.total {
+                pb.set_length(total);
+            }
+            pb.set_position(p.downloaded);
+        })?;
 
-    #[cfg(feature = "indicatif")]
-    pb.finish_with_message("Download complete");
+        pb.finish_with_message("Download complete");
+    }
 
     #[cfg(not(feature = "indicatif"))]
-    println!("Download complete");
+    {
+        download(&mut |_| {})?;
+        println!("Download complete");
+    }
 
     Ok(())
 }
@@ -78,66 +602,257 @@ fn download_file(url: &str, path: &Path) -> Result<()> {
 ///
 /// # Arguments
 ///
-/// * `model_name` - The name of the model to download (tiny, base, small, medium, large)
+/// * `model` - The model to download
 ///
 /// # Returns
 ///
-/// The path to the downloaded model
+/// The path to the downloaded model. Newly downloaded files are verified
+/// against the model's published SHA-1 digest.
 ///
 /// # Examples
 ///
 /// ```no_run
-/// use whisper_wrapper_rust::download_model;
+/// use whisper_wrapper_rust::{download_model, Model};
 ///
 /// fn main() -> Result<(), Box<dyn std::error::Error>> {
-///     let model_path = download_model("base")?;
+///     let model_path = download_model(Model::BaseEn)?;
 ///     println!("Model downloaded to {:?}", model_path);
 ///     Ok(())
 /// }
 /// ```
-pub fn download_model(model_name: &str) -> Result<PathBuf> {
-    let models_dir = get_models_dir();
-
-    let (_, url) = MODEL_URLS
-        .iter()
-        .find(|(name, _)| *name == model_name)
-        .ok_or_else(|| anyhow!("Invalid model name: {}", model_name))?;
+pub fn download_model(model: Model) -> Result<PathBuf> {
+    download_model_with_config(model, &DownloadConfig::default())
+}
 
-    let model_filename = format!("ggml-{}.bin", model_name);
-    let model_path = models_dir.join(&model_filename);
+/// Download a Whisper model with custom network settings
+///
+/// See [`DownloadConfig`] for the available settings.
+pub fn download_model_with_config(model: Model, config: &DownloadConfig) -> Result<PathBuf> {
+    let cache = ModelCache::new()?;
+    let model_path = cache.dir().join(model.filename());
 
     if model_path.exists() {
         println!("Model already exists at {:?}", model_path);
+        cache.touch(model.name())?;
         return Ok(model_path);
     }
 
-    download_file(url, &model_path)?;
+    if is_offline() {
+        return Err(WhisperError::Offline(model.name().to_string()));
+    }
+
+    println!("Downloading {} model", model);
+    with_progress_bar(|progress| download_from_sources(model, &model_path, config, progress))?;
+    cache.touch(model.name())?;
     Ok(model_path)
 }
 
-/// List available models that can be downloaded
-pub fn list_available_models() -> Vec<String> {
-    MODEL_URLS
-        .iter()
-        .map(|(name, _)| name.to_string())
+/// Download a Whisper model, reporting progress through a callback
+///
+/// This does not draw a progress bar, so it is suitable for applications
+/// that want to display progress themselves.
+///
+/// # Examples
+///
+/// ```no_run
+/// use whisper_wrapper_rust::{download_model_with_progress, DownloadConfig, Model};
+///
+/// fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let config = DownloadConfig::default();
+///     let model_path = download_model_with_progress(Model::Tiny, &config, |p| {
+///         if let Some(total) = p.total {
+///             eprint!("\r{}/{} bytes", p.downloaded, total);
+///         }
+///     })?;
+///     println!("Model downloaded to {:?}", model_path);
+///     Ok(())
+/// }
+/// ```
+pub fn download_model_with_progress<F>(
+    model: Model,
+    config: &DownloadConfig,
+    mut progress: F,
+) -> Result<PathBuf>
+where
+    F: FnMut(DownloadProgress),
+{
+    let cache = ModelCache::new()?;
+    let model_path = cache.dir().join(model.filename());
+
+    if !model_path.exists() {
+        if is_offline() {
+            return Err(WhisperError::Offline(model.name().to_string()));
+        }
+        download_from_sources(model, &model_path, config, &mut progress)?;
+    }
+
+    cache.touch(model.name())?;
+    Ok(model_path)
+}
+
+/// Download several Whisper models in parallel
+///
+/// Up to [`DownloadConfig::parallel_downloads`] models are fetched at once,
+/// each with its own progress bar. A failed download does not stop the
+/// others; the result for every requested model is returned in order, so
+/// callers can report all failures together. Duplicate models are only
+/// downloaded once.
+///
+/// # Examples
+///
+/// ```no_run
+/// use whisper_wrapper_rust::{download_models, DownloadConfig, Model};
+///
+/// let models = [Model::Tiny, Model::Base, Model::Small];
+/// for (model, result) in download_models(&models, &DownloadConfig::default()) {
+///     match result {
+///         Ok(path) => println!("{}: {:?}", model, path),
+///         Err(e) => eprintln!("{}: {}", model, e),
+///     }
+/// }
+/// ```
+pub fn download_models(models: &[Model], config: &DownloadConfig) -> Vec<(Model, Result<PathBuf>)> {
+    let mut unique = Vec::new();
+    for &model in models {
+        if !unique.contains(&model) {
+            unique.push(model);
+        }
+    }
+
+    let cache = match ModelCache::new() {
+        Ok(cache) => cache,
+        Err(e) => {
+            let message = e.to_string();
+            return unique
+                .into_iter()
+                .map(|m| (m, Err(WhisperError::Other(message.clone()))))
+                .collect();
+        }
+    };
+
+    #[cfg(feature = "indicatif")]
+    let bars = MultiProgress::new();
+
+    let next = AtomicUsize::new(0);
+    let results: Mutex<Vec<Option<Result<PathBuf>>>> =
+        Mutex::new(unique.iter().map(|_| None).collect());
+
+    // Each worker takes the next model off the list until none are left
+    thread::scope(|scope| {
+        for _ in 0..config.parallel_downloads.min(unique.len()) {
+            scope.spawn(|| loop {
+                let i = next.fetch_add(1, Ordering::SeqCst);
+                let Some(&model) = unique.get(i) else {
+                    break;
+                };
+
+                let model_path = cache.dir().join(model.filename());
+                let result = if model_path.exists() {
+                    Ok(model_path)
+                } else if is_offline() {
+                    Err(WhisperError::Offline(model.name().to_string()))
+                } else {
+                    #[cfg(feature = "indicatif")]
+                    let result = {
+                        let pb = bars.add(ProgressBar::new(0));
+                        pb.set_style(
+                            ProgressStyle::default_bar()
+                                .template(
+                                    "{msg:<22} [{bar:30.cyan/blue}] {bytes}/{total_bytes} ({eta})",
+                                )
+                                .unwrap()
+                                .progress_chars("#>-"),
+                        );
+                        pb.set_message(model.name());
+
+                        let result = download_from_sources(model, &model_path, config, &mut |p| {
+                            if let Some(total) = p.total {
+                                pb.set_length(total);
+                            }
+                            pb.set_position(p.downloaded);
+                        });
+
+                        match &result {
+                            Ok(()) => pb.finish(),
+                            Err(_) => pb.abandon_with_message(format!("{} (failed)", model.name())),
+                        }
+                        result
+                    };
+
+                    #[cfg(not(feature = "indicatif"))]
+                    let result = download_from_sources(model, &model_path, config, &mut |_| {});
+
+                    result.map(|()| model_path)
+                };
+
+                results.lock().unwrap()[i] = Some(result);
+            });
+        }
+    });
+
+    // The cache index is only updated from this thread, as concurrent
+    // updates could overwrite each other
+    let results = results.into_inner().unwrap();
+    unique
+        .into_iter()
+        .zip(results)
+        .map(|(model, result)| {
+            let result = result.expect("every model is downloaded by a worker");
+            let result = result.and_then(|path| cache.touch(model.name()).map(|()| path));
+            (model, result)
+        })
         .collect()
 }
 
+/// Download a model that is not in the registry, such as a fine-tuned model
+///
+/// The file is stored in the models directory as `ggml-<name>.bin`, so it
+/// can be found the same way as the built-in models.
+///
+/// # Examples
+///
+/// ```no_run
+/// use whisper_wrapper_rust::{download_custom_model, DownloadConfig};
+///
+/// fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let model_path = download_custom_model(
+///         "medical-small",
+///         "https://artifactory.example.com/whisper/ggml-medical-small.bin",
+///         &DownloadConfig::default(),
+///     )?;
+///     println!("Model downloaded to {:?}", model_path);
+///     Ok(())
+/// }
+/// ```
+pub fn download_custom_model(name: &str, url: &str, config: &DownloadConfig) -> Result<PathBuf> {
+    let cache = ModelCache::new()?;
+    let model_path = cache.dir().join(format!("ggml-{}.bin", name));
+
+    if !model_path.exists() {
+        if is_offline() {
+            return Err(WhisperError::Offline(name.to_string()));
+        }
+        let client = config.blocking_client()?;
+        with_progress_bar(|progress| {
+            download_with_retries(&client, config, url, &model_path, None, progress)
+        })?;
+    }
+
+    cache.touch(name)?;
+    Ok(model_path)
+}
+
+/// List available models that can be downloaded
+pub fn list_available_models() -> Vec<Model> {
+    Model::all().collect()
+}
+
 /// List downloaded models
 pub fn list_downloaded_models() -> Result<Vec<PathBuf>> {
-    let models_dir = get_models_dir();
-    let entries = fs::read_dir(models_dir)?;
-
-    let models = entries
-        .filter_map(|entry| {
-            let entry = entry.ok()?;
-            let path = entry.path();
-            if path.is_file() && path.extension()? == "bin" {
-                Some(path)
-            } else {
-                None
-            }
-        })
+    let models = ModelCache::new()?
+        .list()?
+        .into_iter()
+        .map(|m| m.path)
         .collect();
 
     Ok(models)
@@ -21,6 +21,67 @@ pub enum WhisperError {
     #[error("Failed to download model: {0}")]
     DownloadError(String),
 
+    /// Error when the server answers a download with an unsuccessful status
+    #[cfg(feature = "download")]
+    #[error("HTTP {status} while downloading {url}")]
+    HttpError { url: String, status: u16 },
+
+    /// Error when a model file does not match its published checksum
+    #[error("Checksum mismatch for {path}: expected {expected}, got {actual}")]
+    ChecksumMismatch {
+        path: PathBuf,
+        expected: String,
+        actual: String,
+    },
+
+    /// Error when a model name is not in the registry
+    #[error("Unknown model: {0}")]
+    UnknownModel(String),
+
+    /// Error when a model is not cached and cannot be downloaded
+    #[error("Model {0} is not in the model cache and downloads are disabled (offline mode)")]
+    Offline(String),
+
+    /// Error when the whisper.cpp binary cannot be run or fails
+    #[error("whisper.cpp command failed: {0}")]
+    CommandError(String),
+
+    /// Error when the whisper.cpp binary cannot be found
+    #[error(
+        "Could not find the whisper.cpp binary. Set {} or add whisper-cli to PATH. Tried:{}",
+        crate::commands::WHISPER_CPP_BIN_ENV,
+        list_paths(.tried)
+    )]
+    BinaryNotFound { tried: Vec<PathBuf> },
+
+    /// Error when the whisper.cpp binary does not support an option
+    #[error(
+        "The whisper.cpp binary at {} ({}) does not support {flag}",
+        .binary.display(),
+        .version.as_deref().map(|v| format!("version {}", v)).unwrap_or_else(|| "unknown version".to_string())
+    )]
+    UnsupportedFlag {
+        flag: String,
+        binary: PathBuf,
+        version: Option<String>,
+    },
+
+    /// Error when the whisper.cpp binary runs longer than its timeout
+    #[error("whisper.cpp command timed out after {0:?}")]
+    Timeout(std::time::Duration),
+
+    /// Error when a running whisper.cpp binary is cancelled
+    #[error("whisper.cpp command was cancelled")]
+    Cancelled,
+
+    /// Error when a token id is outside the model vocabulary
+    #[error("Invalid token id: {0}")]
+    InvalidToken(i32),
+
+    /// Error when whisper.cpp doesn't know a language
+    #[error("Unknown language: {0}")]
+    UnknownLanguage(String),
+
     /// Error when the model file is not found
     #[error("Model file not found: {0}")]
     ModelNotFound(PathBuf),
@@ -42,6 +103,14 @@ pub enum WhisperError {
     Other(String),
 }
 
+// Format paths as an indented list, one per line
+fn list_paths(paths: &[PathBuf]) -> String {
+    paths
+        .iter()
+        .map(|p| format!("\n  {}", p.display()))
+        .collect()
+}
+
 #[cfg(feature = "download")]
 impl From<reqwest::Error> for WhisperError {
     fn from(err: reqwest::Error) -> Self {
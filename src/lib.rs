@@ -19,7 +19,7 @@
 //! fn main() -> Result<(), Box<dyn std::error::Error>> {
 //!     // Download a model (if download feature is enabled)
 //!     #[cfg(feature = "download")]
-//!     let model_path = whisper_wrapper_rust::download_model("base")?;
+//!     let model_path = whisper_wrapper_rust::download_model(whisper_wrapper_rust::Model::Base)?;
 //!     
 //!     #[cfg(not(feature = "download"))]
 //!     let model_path = Path::new("path/to/model.bin");
@@ -34,31 +34,65 @@
 //!         .output_format("txt");
 //!     
 //!     // Transcribe audio
-//!     let result = ctx.transcribe(Path::new("audio.mp3"), &params)?;
+//!     let result = ctx.transcribe(Path::new("audio.wav"), &params)?;
 //!     
 //!     // Print the result
-//!     println!("{}", result);
+//!     println!("{}", result.format("txt")?);
 //!     
 //!     Ok(())
 //! }
 //! ```
 
+mod audio;
 mod bindings;
+mod cache;
+mod checkout;
 pub mod commands;
 mod context;
 mod error;
+mod mel;
+mod model;
 mod params;
+mod probe;
+mod state;
+mod system;
+mod transcriber;
+mod transcript;
 
 #[cfg(feature = "download")]
 mod download;
 
+#[cfg(feature = "async-download")]
+mod async_download;
+
+pub use audio::read_wav;
+pub use cache::{
+    is_offline, models_dir, resolve_model, set_models_dir, set_offline, verify_model, CachedModel,
+    ChecksumStatus, ModelCache, MODELS_DIR_ENV, OFFLINE_ENV,
+};
+#[allow(deprecated)]
 pub use commands::execute_whisper_cpp;
+pub use commands::{CancelHandle, OutputFormat, WhisperCommand, WhisperEvent};
 pub use context::WhisperContext;
 pub use error::WhisperError;
-pub use params::WhisperParams;
+pub use mel::Mel;
+pub use model::{Model, Quantization};
+pub use params::{ContextParams, DtwPreset, WhisperParams};
+pub use probe::BinaryInfo;
+pub use state::WhisperState;
+pub use system::{backends, build_features, enabled_features, system_info};
+pub use transcriber::{SubprocessTranscriber, Transcriber};
+pub use transcript::{Segment, Token, TokenId, Transcript};
 
 #[cfg(feature = "download")]
-pub use download::download_model;
+pub use download::{
+    add_mirror, clear_mirrors, download_custom_model, download_model, download_model_with_config,
+    download_model_with_progress, download_models, download_url, list_available_models,
+    list_downloaded_models, DownloadConfig, DownloadProgress, HF_TOKEN_ENV, MODELS_MIRROR_ENV,
+};
+
+#[cfg(feature = "async-download")]
+pub use async_download::{download_model_async, download_url_async};
 
 // Re-export Result type
 pub type Result<T> = std::result::Result<T, WhisperError>;
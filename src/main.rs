@@ -1,18 +1,31 @@
 use anyhow::Result;
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
 use env_logger;
 use log::info;
 use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
 
-use whisper_wrapper_rust::commands;
-use whisper_wrapper_rust::{WhisperContext, WhisperParams};
+use whisper_wrapper_rust::{
+    resolve_model, ChecksumStatus, ModelCache, OutputFormat, SubprocessTranscriber, Transcriber,
+    WhisperCommand, WhisperContext, WhisperEvent, WhisperParams,
+};
 
 #[cfg(feature = "download")]
-use whisper_wrapper_rust::download_model;
+use whisper_wrapper_rust::{
+    download_model_with_config, download_models, list_available_models, DownloadConfig, Model,
+};
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
 struct Cli {
+    /// Directory to store models in (overrides WHISPER_MODELS_DIR)
+    #[arg(long, global = true)]
+    models_dir: Option<PathBuf>,
+
+    /// Never download models, only use the ones already in the cache
+    #[arg(long, global = true)]
+    offline: bool,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -22,9 +35,50 @@ enum Commands {
     /// Download a Whisper model
     #[cfg(feature = "download")]
     Download {
-        /// Model size to download (tiny, base, small, medium, large)
-        #[arg(short, long, default_value = "base")]
-        model: String,
+        /// Models to download, comma-separated (e.g. tiny,base.en,small-q5_1,large-v3-turbo)
+        #[arg(short, long, value_delimiter = ',', default_value = "base")]
+        model: Vec<Model>,
+
+        /// Maximum number of models to download at once
+        #[arg(short, long, default_value_t = 3)]
+        jobs: usize,
+
+        /// List the available models instead of downloading
+        #[arg(long)]
+        list: bool,
+
+        /// Seconds to wait for data before giving up on a transfer
+        #[arg(long, default_value_t = 60)]
+        timeout: u64,
+
+        /// Seconds to wait for a connection
+        #[arg(long, default_value_t = 30)]
+        connect_timeout: u64,
+
+        /// Number of times a failed download is retried
+        #[arg(long, default_value_t = 3)]
+        retries: u32,
+
+        /// Proxy URL for all requests (defaults to the system proxy settings)
+        #[arg(long)]
+        proxy: Option<String>,
+
+        /// Bearer token for private repositories (defaults to HF_TOKEN for huggingface.co)
+        #[arg(long)]
+        token: Option<String>,
+
+        /// User-Agent header to send
+        #[arg(long)]
+        user_agent: Option<String>,
+    },
+
+    /// Verify every downloaded model against its published checksum
+    Verify,
+
+    /// Manage the downloaded models
+    Models {
+        #[command(subcommand)]
+        command: ModelsCommand,
     },
 
     /// Transcribe audio to text
@@ -33,9 +87,9 @@ enum Commands {
         #[arg(short, long)]
         audio: PathBuf,
 
-        /// Path to the model file
+        /// Path to the model file, or a model name such as base.en
         #[arg(short, long)]
-        model: PathBuf,
+        model: String,
 
         /// Language to use for transcription (auto for auto-detect)
         #[arg(short, long, default_value = "auto")]
@@ -52,6 +106,14 @@ enum Commands {
         /// Output file path (defaults to audio filename with new extension)
         #[arg(short, long)]
         output: Option<PathBuf>,
+
+        /// Transcription backend to use
+        #[arg(long, value_enum, default_value_t = Backend::Ffi)]
+        backend: Backend,
+
+        /// Path to the whisper.cpp binary for the subprocess backend (optional)
+        #[arg(short = 'b', long)]
+        binary: Option<PathBuf>,
     },
 
     /// Execute whisper.cpp binary directly
@@ -60,9 +122,9 @@ enum Commands {
         #[arg(short, long)]
         audio: PathBuf,
 
-        /// Path to the model file
+        /// Path to the model file, or a model name such as base.en
         #[arg(short, long)]
-        model: PathBuf,
+        model: String,
 
         /// Language to use for transcription (auto for auto-detect)
         #[arg(short, long, default_value = "auto")]
@@ -72,7 +134,7 @@ enum Commands {
         #[arg(short, long)]
         translate: bool,
 
-        /// Output format (txt, srt, vtt, json)
+        /// Output format (txt, srt, vtt, lrc, csv, json, json-full)
         #[arg(short, long, default_value = "txt")]
         format: String,
 
@@ -84,22 +146,289 @@ enum Commands {
         #[arg(short, long)]
         output: Option<PathBuf>,
 
+        /// Kill whisper.cpp if it runs longer than this many seconds
+        #[arg(long)]
+        timeout: Option<u64>,
+
         /// Additional arguments to pass to whisper.cpp
         #[arg(last = true)]
         additional_args: Vec<String>,
     },
 }
 
+/// How audio is transcribed
+#[derive(Clone, Copy, ValueEnum)]
+enum Backend {
+    /// Call whisper.cpp in-process (16 kHz WAV input only)
+    Ffi,
+    /// Run the whisper.cpp command-line binary
+    Subprocess,
+}
+
+#[derive(Subcommand)]
+enum ModelsCommand {
+    /// List downloaded models, most recently used first
+    List {
+        /// Also check every model against its published checksum (slow)
+        #[arg(long)]
+        verify: bool,
+    },
+
+    /// Remove a downloaded model
+    Rm {
+        /// Name of the model to remove (e.g. base.en)
+        name: String,
+    },
+
+    /// Remove the least recently used models until the cache fits a size budget
+    Prune {
+        /// Maximum size of the cache (e.g. 500M, 4G)
+        #[arg(long, value_parser = parse_size)]
+        max_size: u64,
+    },
+
+    /// Show the disk space used by the model cache
+    Du,
+}
+
+// Parse a size such as "512M" or "4G" into bytes
+fn parse_size(s: &str) -> std::result::Result<u64, String> {
+    let s = s.trim();
+    let (number, unit) = match s.find(|c: char| c.is_ascii_alphabetic()) {
+        Some(i) => s.split_at(i),
+        None => (s, ""),
+    };
+
+    let multiplier: u64 = match unit
+        .to_ascii_uppercase()
+        .trim_end_matches("IB")
+        .trim_end_matches('B')
+    {
+        "" => 1,
+        "K" => 1024,
+        "M" => 1024 * 1024,
+        "G" => 1024 * 1024 * 1024,
+        "T" => 1024 * 1024 * 1024 * 1024,
+        _ => return Err(format!("Invalid size unit: {}", unit)),
+    };
+
+    let number: f64 = number
+        .trim()
+        .parse()
+        .map_err(|_| format!("Invalid size: {}", s))?;
+
+    Ok((number * multiplier as f64) as u64)
+}
+
+// Format a size in bytes for display
+fn format_size(bytes: u64) -> String {
+    const UNITS: &[&str] = &["B", "KiB", "MiB", "GiB", "TiB"];
+
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+// Format how long ago a time was for display
+fn format_age(time: Option<SystemTime>) -> String {
+    let secs = match time.and_then(|t| t.elapsed().ok()) {
+        Some(elapsed) => elapsed.as_secs(),
+        None => return "-".to_string(),
+    };
+
+    match secs {
+        0..=59 => "just now".to_string(),
+        60..=3599 => format!("{}m ago", secs / 60),
+        3600..=86399 => format!("{}h ago", secs / 3600),
+        _ => format!("{}d ago", secs / 86400),
+    }
+}
+
 fn main() -> Result<()> {
     env_logger::init();
     let cli = Cli::parse();
 
+    if let Some(dir) = &cli.models_dir {
+        whisper_wrapper_rust::set_models_dir(Some(dir));
+    }
+
+    if cli.offline {
+        whisper_wrapper_rust::set_offline(true);
+    }
+
     match &cli.command {
         #[cfg(feature = "download")]
-        Commands::Download { model } => {
-            info!("Downloading {} model...", model);
-            let model_path = download_model(model)?;
-            println!("Model downloaded successfully to {:?}!", model_path);
+        Commands::Download {
+            model,
+            jobs,
+            list,
+            timeout,
+            connect_timeout,
+            retries,
+            proxy,
+            token,
+            user_agent,
+        } => {
+            if *list {
+                println!(
+                    "{:<22} {:>10} {:>10} {:>6} {:>13}",
+                    "MODEL", "SIZE (MB)", "RAM (MB)", "QUANT", "MULTILINGUAL"
+                );
+                for model in list_available_models() {
+                    println!(
+                        "{:<22} {:>10} {:>10} {:>6} {:>13}",
+                        model.name(),
+                        model.size_mb(),
+                        model.ram_mb(),
+                        model
+                            .quantization()
+                            .map(|q| q.to_string())
+                            .unwrap_or_else(|| "-".to_string()),
+                        if model.is_multilingual() { "yes" } else { "no" }
+                    );
+                }
+                return Ok(());
+            }
+
+            let mut config = DownloadConfig::new()
+                .read_timeout(Duration::from_secs(*timeout))
+                .connect_timeout(Duration::from_secs(*connect_timeout))
+                .retries(*retries)
+                .parallel_downloads(*jobs);
+            if let Some(proxy) = proxy {
+                config = config.proxy(proxy);
+            }
+            if let Some(token) = token {
+                config = config.bearer_token(token);
+            }
+            if let Some(user_agent) = user_agent {
+                config = config.user_agent(user_agent);
+            }
+
+            if let [model] = model.as_slice() {
+                info!("Downloading {} model...", model);
+                let model_path = download_model_with_config(*model, &config)?;
+                println!("Model downloaded successfully to {:?}!", model_path);
+                return Ok(());
+            }
+
+            let results = download_models(model, &config);
+            let mut failures = 0;
+
+            println!();
+            println!("{:<22} {:<7} PATH / ERROR", "MODEL", "STATUS");
+            for (model, result) in &results {
+                match result {
+                    Ok(path) => println!("{:<22} {:<7} {}", model.name(), "ok", path.display()),
+                    Err(e) => {
+                        failures += 1;
+                        println!("{:<22} {:<7} {}", model.name(), "FAILED", e);
+                    }
+                }
+            }
+
+            if failures > 0 {
+                return Err(anyhow::anyhow!(
+                    "{} of {} downloads failed",
+                    failures,
+                    results.len()
+                ));
+            }
+        }
+
+        Commands::Verify => {
+            let cache = ModelCache::new()?;
+            let mut failures = 0;
+
+            for model in cache.list()? {
+                match cache.verify(&model)? {
+                    ChecksumStatus::Valid => println!("{:<24} OK", model.name),
+                    ChecksumStatus::Mismatch => {
+                        failures += 1;
+                        println!("{:<24} MISMATCH", model.name);
+                    }
+                    ChecksumStatus::Unknown => {
+                        println!("{:<24} SKIPPED (not a known model)", model.name)
+                    }
+                }
+            }
+
+            if failures > 0 {
+                return Err(anyhow::anyhow!(
+                    "{} model(s) failed checksum verification",
+                    failures
+                ));
+            }
+        }
+
+        Commands::Models { command } => {
+            let cache = ModelCache::new()?;
+
+            match command {
+                ModelsCommand::List { verify } => {
+                    println!(
+                        "{:<24} {:>10} {:>6} {:>9} {:>10}",
+                        "NAME", "SIZE", "QUANT", "CHECKSUM", "LAST USED"
+                    );
+                    for model in cache.list()? {
+                        let checksum = if *verify {
+                            match cache.verify(&model)? {
+                                ChecksumStatus::Valid => "ok",
+                                ChecksumStatus::Mismatch => "MISMATCH",
+                                ChecksumStatus::Unknown => "unknown",
+                            }
+                        } else {
+                            "-"
+                        };
+
+                        println!(
+                            "{:<24} {:>10} {:>6} {:>9} {:>10}",
+                            model.name,
+                            format_size(model.size),
+                            model
+                                .quantization()
+                                .map(|q| q.to_string())
+                                .unwrap_or_else(|| "-".to_string()),
+                            checksum,
+                            format_age(model.last_used)
+                        );
+                    }
+                }
+
+                ModelsCommand::Rm { name } => match cache.remove(name)? {
+                    Some(model) => println!("Removed {} ({})", model.name, format_size(model.size)),
+                    None => return Err(anyhow::anyhow!("Model {} is not downloaded", name)),
+                },
+
+                ModelsCommand::Prune { max_size } => {
+                    let removed = cache.prune(*max_size)?;
+                    for model in &removed {
+                        println!("Removed {} ({})", model.name, format_size(model.size));
+                    }
+                    println!(
+                        "Pruned {} model(s), cache is now {}",
+                        removed.len(),
+                        format_size(cache.disk_usage()?)
+                    );
+                }
+
+                ModelsCommand::Du => {
+                    println!(
+                        "{}\t{}",
+                        format_size(cache.disk_usage()?),
+                        cache.dir().display()
+                    );
+                }
+            }
         }
 
         Commands::Transcribe {
@@ -109,11 +438,23 @@ fn main() -> Result<()> {
             translate,
             format,
             output,
+            backend,
+            binary,
         } => {
             info!("Transcribing audio file: {:?}", audio);
 
-            // Create the context
-            let mut ctx = WhisperContext::new(model)?;
+            // Create the transcriber
+            let model_path = resolve_model(model)?;
+            let mut transcriber: Box<dyn Transcriber> = match backend {
+                Backend::Ffi => Box::new(WhisperContext::new(&model_path)?),
+                Backend::Subprocess => {
+                    let mut subprocess = SubprocessTranscriber::new(&model_path);
+                    if let Some(binary) = binary {
+                        subprocess = subprocess.binary(binary);
+                    }
+                    Box::new(subprocess)
+                }
+            };
 
             // Set parameters
             let params = WhisperParams::new()
@@ -122,7 +463,7 @@ fn main() -> Result<()> {
                 .output_format(format);
 
             // Transcribe
-            let result = ctx.transcribe(audio, &params)?;
+            let result = transcriber.transcribe(audio, &params)?;
 
             // Determine output path
             let output_path = output.clone().unwrap_or_else(|| {
@@ -132,7 +473,7 @@ fn main() -> Result<()> {
             });
 
             // Write the result to the output file
-            std::fs::write(&output_path, result)?;
+            std::fs::write(&output_path, result.format(format)?)?;
 
             println!("Transcription complete! Output saved to {:?}", output_path);
         }
@@ -145,6 +486,7 @@ fn main() -> Result<()> {
             format,
             binary,
             output,
+            timeout,
             additional_args,
         } => {
             info!("Executing whisper.cpp directly for audio file: {:?}", audio);
@@ -166,20 +508,34 @@ fn main() -> Result<()> {
                 }
             }
 
-            // Execute whisper.cpp directly
-            let result = commands::execute_whisper_cpp(
-                model,
-                audio,
-                Some(&output_path),
-                Some(language),
-                *translate,
-                Some(format),
-                additional_args.clone(),
-                binary.clone().map(|p| p.clone()),
-            )?;
-
-            println!("Direct execution output:\n{}", result);
-            println!("Transcription complete! Output saved to {:?}", output_path);
+            // Execute whisper.cpp directly; it adds the extension to the output file itself
+            let format: OutputFormat = format.parse()?;
+            let output_base = output_path.with_extension("");
+            let model_path = resolve_model(model)?;
+            let mut cmd = WhisperCommand::new(&model_path, audio)
+                .language(language)
+                .translate(*translate)
+                .output_format(format)
+                .output_file(&output_base)
+                .args(additional_args);
+            if let Some(binary_path) = binary {
+                cmd = cmd.binary(binary_path);
+            }
+            if let Some(timeout) = timeout {
+                cmd = cmd.timeout(Duration::from_secs(*timeout));
+            }
+
+            // Print segments as whisper.cpp produces them
+            cmd.stream(|event| match event {
+                WhisperEvent::Segment(segment) => println!("{}", segment.text),
+                WhisperEvent::Stderr(line) => log::debug!("{}", line),
+                _ => {}
+            })?;
+
+            println!(
+                "Transcription complete! Output saved to {:?}",
+                output_base.with_extension(format.extension())
+            );
         }
     }
 
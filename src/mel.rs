@@ -0,0 +1,281 @@
+use std::f64::consts::PI;
+
+use crate::error::WhisperError;
+use crate::Result;
+
+// Audio parameters the whisper models are trained with
+const SAMPLE_RATE: usize = 16000;
+const N_FFT: usize = 400;
+const HOP_LENGTH: usize = 160;
+
+// Frequency bins from 0 Hz to the Nyquist frequency
+const N_BINS: usize = N_FFT / 2 + 1;
+
+// whisper.cpp pads the audio with 30 seconds of silence
+const PAD_SAMPLES: usize = SAMPLE_RATE * 30;
+
+// The start of the audio is mirrored into the first half frame, which needs
+// this many samples. whisper_pcm_to_mel reads past the end of shorter input.
+pub(crate) const MIN_SAMPLES: usize = N_FFT / 2 + 1;
+
+// Log power of silence
+const LOG_FLOOR: f64 = -10.0;
+
+/// A log-mel spectrogram, the input of the whisper encoder
+///
+/// Values are stored band by band, `data[band * n_len + frame]`, as in
+/// whisper.cpp. Frames are 10 ms apart.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mel {
+    /// Number of mel bands
+    n_mel: usize,
+
+    /// Number of frames
+    n_len: usize,
+
+    /// The values, band by band
+    data: Vec<f32>,
+}
+
+impl Mel {
+    /// Create a spectrogram from `n_mel` bands of equal length, one after the other
+    pub fn new(data: Vec<f32>, n_mel: usize) -> Result<Self> {
+        if n_mel == 0 || !data.len().is_multiple_of(n_mel) {
+            return Err(WhisperError::Other(format!(
+                "{} values can't be split into {} mel bands",
+                data.len(),
+                n_mel
+            )));
+        }
+
+        Ok(Self {
+            n_len: data.len() / n_mel,
+            n_mel,
+            data,
+        })
+    }
+
+    /// Compute the spectrogram of 16 kHz mono samples in Rust
+    ///
+    /// This follows `whisper_pcm_to_mel`, including the 30 seconds of silence
+    /// appended to the audio, and matches it up to rounding, so it can be used
+    /// to check whisper.cpp or to feed
+    /// [`WhisperState::set_mel`](crate::WhisperState::set_mel). Models use 80
+    /// mel bands, except large-v3 which uses 128, see
+    /// [`WhisperContext::n_mels`](crate::WhisperContext::n_mels).
+    pub fn from_pcm(samples: &[f32], n_mel: usize) -> Self {
+        // Very short input is padded with silence, as in WhisperState::pcm_to_mel
+        let mut samples = samples.to_vec();
+        if samples.len() < MIN_SAMPLES {
+            samples.resize(MIN_SAMPLES, 0.0);
+        }
+
+        let pad = N_FFT / 2;
+        let mut padded = vec![0.0f32; samples.len() + PAD_SAMPLES + 2 * pad];
+        padded[pad..pad + samples.len()].copy_from_slice(&samples);
+        for i in 0..pad {
+            padded[i] = samples[pad - i];
+        }
+
+        let n_len = (padded.len() - N_FFT) / HOP_LENGTH;
+        let mut data = vec![LOG_FLOOR as f32; n_mel * n_len];
+
+        let tables = Tables::new();
+        let filters = mel_filters(n_mel);
+
+        // Frames past the audio are silent and keep the floor value
+        let n_audio = samples.len() + pad;
+        let n_frames = (n_audio / HOP_LENGTH + 1).min(n_len);
+
+        let mut frame = vec![0.0f32; N_FFT];
+        let mut spectrum = vec![0.0f32; 2 * N_FFT];
+        for i in 0..n_frames {
+            let offset = i * HOP_LENGTH;
+            let n = N_FFT.min(n_audio - offset);
+            for j in 0..N_FFT {
+                frame[j] = if j < n {
+                    tables.hann[j] * padded[offset + j]
+                } else {
+                    0.0
+                };
+            }
+
+            fft(&frame, &mut spectrum, &tables);
+
+            let power: Vec<f32> = (0..N_BINS)
+                .map(|j| {
+                    spectrum[2 * j] * spectrum[2 * j] + spectrum[2 * j + 1] * spectrum[2 * j + 1]
+                })
+                .collect();
+
+            for (band, weights) in filters.iter().enumerate() {
+                let sum: f64 = weights
+                    .iter()
+                    .zip(&power)
+                    .map(|(w, p)| (w * p) as f64)
+                    .sum();
+                data[band * n_len + i] = sum.max(1e-10).log10() as f32;
+            }
+        }
+
+        // Keep 80 dB of dynamic range and scale to about [-1, 1]
+        let max = data.iter().copied().fold(f32::MIN, f32::max) as f64;
+        let floor = max - 8.0;
+        for value in &mut data {
+            *value = (((*value as f64).max(floor) + 4.0) / 4.0) as f32;
+        }
+
+        Self { n_mel, n_len, data }
+    }
+
+    /// Get the number of mel bands
+    pub fn get_n_mel(&self) -> usize {
+        self.n_mel
+    }
+
+    /// Get the number of frames, including the padding
+    pub fn get_n_len(&self) -> usize {
+        self.n_len
+    }
+
+    /// Get the values, band by band
+    pub fn get_data(&self) -> &[f32] {
+        &self.data
+    }
+
+    /// Get the value of a band in a frame
+    pub fn get(&self, band: usize, frame: usize) -> Option<f32> {
+        if band >= self.n_mel || frame >= self.n_len {
+            return None;
+        }
+        Some(self.data[band * self.n_len + frame])
+    }
+
+    /// Take the values, band by band
+    pub fn into_data(self) -> Vec<f32> {
+        self.data
+    }
+}
+
+// Hann window and sine/cosine tables, computed as whisper.cpp does
+struct Tables {
+    hann: Vec<f32>,
+    sin: Vec<f32>,
+    cos: Vec<f32>,
+}
+
+impl Tables {
+    fn new() -> Self {
+        let angle = |i: usize| 2.0 * PI * i as f64 / N_FFT as f64;
+        Self {
+            // Periodic window, as torch.hann_window
+            hann: (0..N_FFT)
+                .map(|i| (0.5 * (1.0 - (angle(i) as f32).cos() as f64)) as f32)
+                .collect(),
+            sin: (0..N_FFT).map(|i| (angle(i) as f32).sin()).collect(),
+            cos: (0..N_FFT).map(|i| (angle(i) as f32).cos()).collect(),
+        }
+    }
+}
+
+// Fourier transform of real input into interleaved complex output, splitting
+// even sizes in halves and falling back to a plain DFT for odd ones. The
+// length must divide N_FFT.
+fn fft(input: &[f32], out: &mut [f32], tables: &Tables) {
+    let n = input.len();
+    if n == 1 {
+        out[0] = input[0];
+        out[1] = 0.0;
+        return;
+    }
+    if n % 2 == 1 {
+        dft(input, out, tables);
+        return;
+    }
+
+    let half = n / 2;
+    let even: Vec<f32> = input.iter().step_by(2).copied().collect();
+    let odd: Vec<f32> = input.iter().skip(1).step_by(2).copied().collect();
+    let mut even_fft = vec![0.0f32; n];
+    let mut odd_fft = vec![0.0f32; n];
+    fft(&even, &mut even_fft, tables);
+    fft(&odd, &mut odd_fft, tables);
+
+    let step = N_FFT / n;
+    for k in 0..half {
+        let re = tables.cos[k * step];
+        let im = -tables.sin[k * step];
+        let (re_odd, im_odd) = (odd_fft[2 * k], odd_fft[2 * k + 1]);
+
+        out[2 * k] = even_fft[2 * k] + re * re_odd - im * im_odd;
+        out[2 * k + 1] = even_fft[2 * k + 1] + re * im_odd + im * re_odd;
+        out[2 * (k + half)] = even_fft[2 * k] - re * re_odd + im * im_odd;
+        out[2 * (k + half) + 1] = even_fft[2 * k + 1] - re * im_odd - im * re_odd;
+    }
+}
+
+fn dft(input: &[f32], out: &mut [f32], tables: &Tables) {
+    let n = input.len();
+    let step = N_FFT / n;
+
+    for k in 0..n {
+        let mut re = 0.0f32;
+        let mut im = 0.0f32;
+        for (j, x) in input.iter().enumerate() {
+            let idx = (k * j * step) % N_FFT;
+            re += x * tables.cos[idx];
+            im -= x * tables.sin[idx];
+        }
+        out[2 * k] = re;
+        out[2 * k + 1] = im;
+    }
+}
+
+// Mel filterbank over the FFT bins, as librosa.filters.mel with the Slaney
+// mel scale and area normalization, which whisper models are trained with
+fn mel_filters(n_mel: usize) -> Vec<Vec<f32>> {
+    let max_mel = hz_to_mel(SAMPLE_RATE as f64 / 2.0);
+    let mel_hz: Vec<f64> = (0..n_mel + 2)
+        .map(|i| mel_to_hz(max_mel * i as f64 / (n_mel + 1) as f64))
+        .collect();
+    let bin_hz = |k: usize| k as f64 * SAMPLE_RATE as f64 / N_FFT as f64;
+
+    (0..n_mel)
+        .map(|band| {
+            let (lower, center, upper) = (mel_hz[band], mel_hz[band + 1], mel_hz[band + 2]);
+            let norm = 2.0 / (upper - lower);
+            (0..N_BINS)
+                .map(|k| {
+                    let rising = (bin_hz(k) - lower) / (center - lower);
+                    let falling = (upper - bin_hz(k)) / (upper - center);
+                    (rising.min(falling).max(0.0) * norm) as f32
+                })
+                .collect()
+        })
+        .collect()
+}
+
+// Slaney mel scale: linear below 1 kHz, logarithmic above
+const MIN_LOG_HZ: f64 = 1000.0;
+const HZ_PER_MEL: f64 = 200.0 / 3.0;
+const MIN_LOG_MEL: f64 = MIN_LOG_HZ / HZ_PER_MEL;
+
+fn log_step() -> f64 {
+    6.4f64.ln() / 27.0
+}
+
+fn hz_to_mel(hz: f64) -> f64 {
+    if hz >= MIN_LOG_HZ {
+        MIN_LOG_MEL + (hz / MIN_LOG_HZ).ln() / log_step()
+    } else {
+        hz / HZ_PER_MEL
+    }
+}
+
+fn mel_to_hz(mel: f64) -> f64 {
+    if mel >= MIN_LOG_MEL {
+        MIN_LOG_HZ * (log_step() * (mel - MIN_LOG_MEL)).exp()
+    } else {
+        mel * HZ_PER_MEL
+    }
+}
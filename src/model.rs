@@ -0,0 +1,414 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::WhisperError;
+use crate::params::DtwPreset;
+
+// Base URL for the ggml models published by whisper.cpp
+const MODEL_BASE_URL: &str = "https://huggingface.co/ggerganov/whisper.cpp/resolve/main";
+
+/// Quantization applied to a model's weights
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Quantization {
+    Q5_0,
+    Q5_1,
+    Q8_0,
+}
+
+impl fmt::Display for Quantization {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Quantization::Q5_0 => "q5_0",
+            Quantization::Q5_1 => "q5_1",
+            Quantization::Q8_0 => "q8_0",
+        };
+        f.write_str(name)
+    }
+}
+
+/// A Whisper model published by whisper.cpp
+#[allow(non_camel_case_types)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Model {
+    Tiny,
+    TinyEn,
+    TinyQ5_1,
+    TinyEnQ5_1,
+    TinyQ8_0,
+    Base,
+    BaseEn,
+    BaseQ5_1,
+    BaseEnQ5_1,
+    BaseQ8_0,
+    Small,
+    SmallEn,
+    SmallQ5_1,
+    SmallEnQ5_1,
+    SmallQ8_0,
+    Medium,
+    MediumEn,
+    MediumQ5_0,
+    MediumEnQ5_0,
+    MediumQ8_0,
+    LargeV1,
+    LargeV2,
+    LargeV2Q5_0,
+    LargeV2Q8_0,
+    LargeV3,
+    LargeV3Q5_0,
+    LargeV3Turbo,
+    LargeV3TurboQ5_0,
+    LargeV3TurboQ8_0,
+}
+
+// Registry entry describing a model
+struct ModelInfo {
+    model: Model,
+    name: &'static str,
+    size_mb: u64,
+    ram_mb: u64,
+    quantization: Option<Quantization>,
+    sha1: &'static str,
+}
+
+// Model registry (sizes in MiB, RAM figures are approximate). The SHA-1
+// digests are the ones published in whisper.cpp's models/README.md.
+const MODELS: &[ModelInfo] = &[
+    ModelInfo {
+        model: Model::Tiny,
+        name: "tiny",
+        size_mb: 75,
+        ram_mb: 273,
+        quantization: None,
+        sha1: "bd577a113a864445d4c299885e0cb97d4ba92b5f",
+    },
+    ModelInfo {
+        model: Model::TinyEn,
+        name: "tiny.en",
+        size_mb: 75,
+        ram_mb: 273,
+        quantization: None,
+        sha1: "c78c86eb1a8faa21b369bcd33207cc90d64ae9df",
+    },
+    ModelInfo {
+        model: Model::TinyQ5_1,
+        name: "tiny-q5_1",
+        size_mb: 31,
+        ram_mb: 180,
+        quantization: Some(Quantization::Q5_1),
+        sha1: "2827a03e495b1ed3048ef28a6a4620537db4ee51",
+    },
+    ModelInfo {
+        model: Model::TinyEnQ5_1,
+        name: "tiny.en-q5_1",
+        size_mb: 31,
+        ram_mb: 180,
+        quantization: Some(Quantization::Q5_1),
+        sha1: "3fb92ec865cbbc769f08137f22470d6b66e071b6",
+    },
+    ModelInfo {
+        model: Model::TinyQ8_0,
+        name: "tiny-q8_0",
+        size_mb: 42,
+        ram_mb: 200,
+        quantization: Some(Quantization::Q8_0),
+        sha1: "19e8118f6652a650569f5a949d962154e01571d9",
+    },
+    ModelInfo {
+        model: Model::Base,
+        name: "base",
+        size_mb: 142,
+        ram_mb: 388,
+        quantization: None,
+        sha1: "465707469ff3a37a2b9b8d8f89f2f99de7299dac",
+    },
+    ModelInfo {
+        model: Model::BaseEn,
+        name: "base.en",
+        size_mb: 142,
+        ram_mb: 388,
+        quantization: None,
+        sha1: "137c40403d78fd54d454da0f9bd998f78703390c",
+    },
+    ModelInfo {
+        model: Model::BaseQ5_1,
+        name: "base-q5_1",
+        size_mb: 57,
+        ram_mb: 250,
+        quantization: Some(Quantization::Q5_1),
+        sha1: "a3733eda680ef76256db5fc5dd9de8629e62c5e7",
+    },
+    ModelInfo {
+        model: Model::BaseEnQ5_1,
+        name: "base.en-q5_1",
+        size_mb: 57,
+        ram_mb: 250,
+        quantization: Some(Quantization::Q5_1),
+        sha1: "d26d7ce5a1b6e57bea5d0431b9c20ae49423c94a",
+    },
+    ModelInfo {
+        model: Model::BaseQ8_0,
+        name: "base-q8_0",
+        size_mb: 78,
+        ram_mb: 290,
+        quantization: Some(Quantization::Q8_0),
+        sha1: "7bb89bb49ed6955013b166f1b6a6c04584a20fbe",
+    },
+    ModelInfo {
+        model: Model::Small,
+        name: "small",
+        size_mb: 466,
+        ram_mb: 852,
+        quantization: None,
+        sha1: "55356645c2b361a969dfd0ef2c5a50d530afd8d5",
+    },
+    ModelInfo {
+        model: Model::SmallEn,
+        name: "small.en",
+        size_mb: 466,
+        ram_mb: 852,
+        quantization: None,
+        sha1: "db8a495a91d927739e50b3fc1cc4c6b8f6c2d022",
+    },
+    ModelInfo {
+        model: Model::SmallQ5_1,
+        name: "small-q5_1",
+        size_mb: 181,
+        ram_mb: 500,
+        quantization: Some(Quantization::Q5_1),
+        sha1: "6fe57ddcfdd1c6b07cdcc73aaf620810ce5fc771",
+    },
+    ModelInfo {
+        model: Model::SmallEnQ5_1,
+        name: "small.en-q5_1",
+        size_mb: 181,
+        ram_mb: 500,
+        quantization: Some(Quantization::Q5_1),
+        sha1: "20f54878d608f94e4a8ee3ae56016571d47cba34",
+    },
+    ModelInfo {
+        model: Model::SmallQ8_0,
+        name: "small-q8_0",
+        size_mb: 252,
+        ram_mb: 600,
+        quantization: Some(Quantization::Q8_0),
+        sha1: "bcad8a2083f4e53d648d586b7dbc0cd673d8afad",
+    },
+    ModelInfo {
+        model: Model::Medium,
+        name: "medium",
+        size_mb: 1533,
+        ram_mb: 2100,
+        quantization: None,
+        sha1: "fd9727b6e1217c2f614f9b698455c4ffd82463b4",
+    },
+    ModelInfo {
+        model: Model::MediumEn,
+        name: "medium.en",
+        size_mb: 1533,
+        ram_mb: 2100,
+        quantization: None,
+        sha1: "8c30f0e44ce9560643ebd10bbe50cd20eafd3723",
+    },
+    ModelInfo {
+        model: Model::MediumQ5_0,
+        name: "medium-q5_0",
+        size_mb: 514,
+        ram_mb: 1000,
+        quantization: Some(Quantization::Q5_0),
+        sha1: "7718d4c1ec62ca96998f058114db418236937276",
+    },
+    ModelInfo {
+        model: Model::MediumEnQ5_0,
+        name: "medium.en-q5_0",
+        size_mb: 514,
+        ram_mb: 1000,
+        quantization: Some(Quantization::Q5_0),
+        sha1: "bb3b5281bddd61605d6fc76bc5b92d8f20284c3b",
+    },
+    ModelInfo {
+        model: Model::MediumQ8_0,
+        name: "medium-q8_0",
+        size_mb: 785,
+        ram_mb: 1300,
+        quantization: Some(Quantization::Q8_0),
+        sha1: "e66645948aff4bebbec71b3485c576f3d63af5d6",
+    },
+    ModelInfo {
+        model: Model::LargeV1,
+        name: "large-v1",
+        size_mb: 2952,
+        ram_mb: 3900,
+        quantization: None,
+        sha1: "b1caaf735c4cc1429223d5a74f0f4d0b9b59a299",
+    },
+    ModelInfo {
+        model: Model::LargeV2,
+        name: "large-v2",
+        size_mb: 2952,
+        ram_mb: 3900,
+        quantization: None,
+        sha1: "0f4c8e34f21cf1a914c59d8b3ce882345ad349d6",
+    },
+    ModelInfo {
+        model: Model::LargeV2Q5_0,
+        name: "large-v2-q5_0",
+        size_mb: 1080,
+        ram_mb: 1900,
+        quantization: Some(Quantization::Q5_0),
+        sha1: "00e39f2196344e901b3a2bd5814807a769bd1630",
+    },
+    ModelInfo {
+        model: Model::LargeV2Q8_0,
+        name: "large-v2-q8_0",
+        size_mb: 1660,
+        ram_mb: 2500,
+        quantization: Some(Quantization::Q8_0),
+        sha1: "da97d6ca8f8ffbeeb5fd147f79010eeea194ba38",
+    },
+    ModelInfo {
+        model: Model::LargeV3,
+        name: "large-v3",
+        size_mb: 2952,
+        ram_mb: 3900,
+        quantization: None,
+        sha1: "ad82bf6a9043ceed055076d0fd39f5f186ff8062",
+    },
+    ModelInfo {
+        model: Model::LargeV3Q5_0,
+        name: "large-v3-q5_0",
+        size_mb: 1081,
+        ram_mb: 1900,
+        quantization: Some(Quantization::Q5_0),
+        sha1: "e6e2ed78495d403bef4b7cff42ef4aaadcfea8de",
+    },
+    ModelInfo {
+        model: Model::LargeV3Turbo,
+        name: "large-v3-turbo",
+        size_mb: 1549,
+        ram_mb: 2300,
+        quantization: None,
+        sha1: "4af2b29d7ec73d781377bfd1758ca957a807e941",
+    },
+    ModelInfo {
+        model: Model::LargeV3TurboQ5_0,
+        name: "large-v3-turbo-q5_0",
+        size_mb: 547,
+        ram_mb: 1100,
+        quantization: Some(Quantization::Q5_0),
+        sha1: "e050f7970618a659205450ad97eb95a18d69c9ee",
+    },
+    ModelInfo {
+        model: Model::LargeV3TurboQ8_0,
+        name: "large-v3-turbo-q8_0",
+        size_mb: 834,
+        ram_mb: 1400,
+        quantization: Some(Quantization::Q8_0),
+        sha1: "01bf15bedffe9f39d65c1b6ff9b687ea91f59e0e",
+    },
+];
+
+impl Model {
+    /// Get every model in the registry
+    pub fn all() -> impl Iterator<Item = Model> {
+        MODELS.iter().map(|info| info.model)
+    }
+
+    fn info(&self) -> &'static ModelInfo {
+        MODELS
+            .iter()
+            .find(|info| info.model == *self)
+            .expect("every model has a registry entry")
+    }
+
+    /// Get the model name as used by whisper.cpp (e.g. "base.en-q5_1")
+    pub fn name(&self) -> &'static str {
+        self.info().name
+    }
+
+    /// Get the file name of the model (e.g. "ggml-base.en-q5_1.bin")
+    pub fn filename(&self) -> String {
+        format!("ggml-{}.bin", self.name())
+    }
+
+    /// Get the download URL of the model
+    pub fn url(&self) -> String {
+        format!("{}/{}", MODEL_BASE_URL, self.filename())
+    }
+
+    /// Get the approximate size on disk in MiB
+    pub fn size_mb(&self) -> u64 {
+        self.info().size_mb
+    }
+
+    /// Get the approximate memory required to run the model in MiB
+    pub fn ram_mb(&self) -> u64 {
+        self.info().ram_mb
+    }
+
+    /// Get the quantization of the model, if any
+    pub fn quantization(&self) -> Option<Quantization> {
+        self.info().quantization
+    }
+
+    /// Get the published SHA-1 digest of the model file, as a lowercase hex string
+    pub fn sha1(&self) -> &'static str {
+        self.info().sha1
+    }
+
+    /// Whether the model supports languages other than English
+    pub fn is_multilingual(&self) -> bool {
+        !self.name().contains(".en")
+    }
+
+    /// Get the DTW alignment heads preset matching this model
+    pub fn dtw_preset(&self) -> DtwPreset {
+        match self {
+            Model::Tiny | Model::TinyQ5_1 | Model::TinyQ8_0 => DtwPreset::Tiny,
+            Model::TinyEn | Model::TinyEnQ5_1 => DtwPreset::TinyEn,
+            Model::Base | Model::BaseQ5_1 | Model::BaseQ8_0 => DtwPreset::Base,
+            Model::BaseEn | Model::BaseEnQ5_1 => DtwPreset::BaseEn,
+            Model::Small | Model::SmallQ5_1 | Model::SmallQ8_0 => DtwPreset::Small,
+            Model::SmallEn | Model::SmallEnQ5_1 => DtwPreset::SmallEn,
+            Model::Medium | Model::MediumQ5_0 | Model::MediumQ8_0 => DtwPreset::Medium,
+            Model::MediumEn | Model::MediumEnQ5_0 => DtwPreset::MediumEn,
+            Model::LargeV1 => DtwPreset::LargeV1,
+            Model::LargeV2 | Model::LargeV2Q5_0 | Model::LargeV2Q8_0 => DtwPreset::LargeV2,
+            Model::LargeV3 | Model::LargeV3Q5_0 => DtwPreset::LargeV3,
+            Model::LargeV3Turbo | Model::LargeV3TurboQ5_0 | Model::LargeV3TurboQ8_0 => {
+                DtwPreset::LargeV3Turbo
+            }
+        }
+    }
+}
+
+impl fmt::Display for Model {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Model {
+    type Err = WhisperError;
+
+    /// Parse a model name such as "base", "small.en" or "large-v3-q5_0"
+    ///
+    /// "large" is accepted as an alias for the latest large model.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.trim();
+        let name = name
+            .strip_prefix("ggml-")
+            .and_then(|n| n.strip_suffix(".bin"))
+            .unwrap_or(name);
+
+        if name == "large" {
+            return Ok(Model::LargeV3);
+        }
+
+        MODELS
+            .iter()
+            .find(|info| info.name == name)
+            .map(|info| info.model)
+            .ok_or_else(|| WhisperError::UnknownModel(s.to_string()))
+    }
+}
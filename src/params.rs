@@ -1,5 +1,8 @@
 use crate::bindings;
+use crate::error::WhisperError;
+use crate::Result;
 use std::collections::HashMap;
+use std::ffi::CStr;
 
 /// Parameters for whisper transcription
 pub struct WhisperParams {
@@ -12,6 +15,12 @@ pub struct WhisperParams {
     /// The output format (txt, srt, vtt, json)
     output_format: String,
 
+    /// The number of threads to use (None for the whisper.cpp default)
+    n_threads: Option<i32>,
+
+    /// Whether to compute the start and end of every token
+    token_timestamps: bool,
+
     /// Additional parameters
     extra_params: HashMap<String, String>,
 }
@@ -22,6 +31,8 @@ impl Default for WhisperParams {
             language: "auto".to_string(),
             translate: false,
             output_format: "txt".to_string(),
+            n_threads: None,
+            token_timestamps: true,
             extra_params: HashMap::new(),
         }
     }
@@ -51,6 +62,22 @@ impl WhisperParams {
         self
     }
 
+    /// Set the number of threads to use
+    pub fn threads(mut self, n_threads: i32) -> Self {
+        self.n_threads = Some(n_threads);
+        self
+    }
+
+    /// Set whether to compute the start and end of every token
+    ///
+    /// Enabled by default, so [`Token`](crate::Token) times from
+    /// [`WhisperContext`](crate::WhisperContext) match those of the
+    /// whisper.cpp binary. Without it whisper.cpp leaves them at zero.
+    pub fn token_timestamps(mut self, enabled: bool) -> Self {
+        self.token_timestamps = enabled;
+        self
+    }
+
     /// Set an additional parameter
     pub fn param(mut self, key: &str, value: &str) -> Self {
         self.extra_params.insert(key.to_string(), value.to_string());
@@ -72,33 +99,225 @@ impl WhisperParams {
         &self.output_format
     }
 
+    /// Get the number of threads
+    pub fn get_threads(&self) -> Option<i32> {
+        self.n_threads
+    }
+
+    /// Get whether token timestamps are computed
+    pub fn get_token_timestamps(&self) -> bool {
+        self.token_timestamps
+    }
+
     /// Get an extra parameter
     pub fn get_param(&self, key: &str) -> Option<&String> {
         self.extra_params.get(key)
     }
 
     /// Convert to whisper_full_params
-    pub(crate) fn to_whisper_params(
-        &self,
-        _ctx: *mut bindings::whisper_context,
-    ) -> bindings::whisper_full_params {
+    ///
+    /// `language` must hold the same language as these parameters and
+    /// outlive the returned value, which points into it.
+    pub(crate) fn to_whisper_params(&self, language: &CStr) -> bindings::whisper_full_params {
         // Get the default parameters using the greedy sampling strategy (0)
         let mut params = unsafe {
             // Use 0 as the sampling strategy (greedy)
             bindings::whisper_full_default_params(0)
         };
 
-        // Set language if not auto
-        if self.language != "auto" {
-            params.language = self.language.as_ptr() as *const i8;
-        }
+        // Set the language, including "auto" which whisper.cpp detects itself
+        params.language = language.as_ptr();
 
         // Set translate
         params.translate = self.translate;
 
+        // Set the thread count if specified
+        if let Some(n_threads) = self.n_threads {
+            params.n_threads = n_threads;
+        }
+
+        // Fill in the start and end of every token
+        params.token_timestamps = self.token_timestamps;
+
         // Set other parameters from extra_params if needed
         // This would require mapping string keys to the appropriate fields in whisper_full_params
 
         params
     }
 }
+
+/// Alignment heads preset used for DTW token-level timestamps
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DtwPreset {
+    /// Pick the top N most attentive heads from the last text layers
+    NTopMost(i32),
+    TinyEn,
+    Tiny,
+    BaseEn,
+    Base,
+    SmallEn,
+    Small,
+    MediumEn,
+    Medium,
+    LargeV1,
+    LargeV2,
+    LargeV3,
+    LargeV3Turbo,
+}
+
+impl DtwPreset {
+    fn to_whisper_preset(self) -> bindings::whisper_alignment_heads_preset {
+        match self {
+            DtwPreset::NTopMost(_) => {
+                bindings::whisper_alignment_heads_preset_WHISPER_AHEADS_N_TOP_MOST
+            }
+            DtwPreset::TinyEn => bindings::whisper_alignment_heads_preset_WHISPER_AHEADS_TINY_EN,
+            DtwPreset::Tiny => bindings::whisper_alignment_heads_preset_WHISPER_AHEADS_TINY,
+            DtwPreset::BaseEn => bindings::whisper_alignment_heads_preset_WHISPER_AHEADS_BASE_EN,
+            DtwPreset::Base => bindings::whisper_alignment_heads_preset_WHISPER_AHEADS_BASE,
+            DtwPreset::SmallEn => bindings::whisper_alignment_heads_preset_WHISPER_AHEADS_SMALL_EN,
+            DtwPreset::Small => bindings::whisper_alignment_heads_preset_WHISPER_AHEADS_SMALL,
+            DtwPreset::MediumEn => {
+                bindings::whisper_alignment_heads_preset_WHISPER_AHEADS_MEDIUM_EN
+            }
+            DtwPreset::Medium => bindings::whisper_alignment_heads_preset_WHISPER_AHEADS_MEDIUM,
+            DtwPreset::LargeV1 => bindings::whisper_alignment_heads_preset_WHISPER_AHEADS_LARGE_V1,
+            DtwPreset::LargeV2 => bindings::whisper_alignment_heads_preset_WHISPER_AHEADS_LARGE_V2,
+            DtwPreset::LargeV3 => bindings::whisper_alignment_heads_preset_WHISPER_AHEADS_LARGE_V3,
+            DtwPreset::LargeV3Turbo => {
+                bindings::whisper_alignment_heads_preset_WHISPER_AHEADS_LARGE_V3_TURBO
+            }
+        }
+    }
+}
+
+/// Parameters used when creating a whisper context
+#[derive(Debug, Clone)]
+pub struct ContextParams {
+    /// Whether to offload to the GPU (ignored by CPU-only builds)
+    use_gpu: bool,
+
+    /// Whether to use flash attention
+    flash_attn: bool,
+
+    /// The GPU device to use
+    gpu_device: i32,
+
+    /// Whether to compute token-level timestamps with DTW
+    dtw_token_timestamps: bool,
+
+    /// The alignment heads preset for DTW, if any
+    dtw_preset: Option<DtwPreset>,
+}
+
+impl Default for ContextParams {
+    fn default() -> Self {
+        Self {
+            use_gpu: true,
+            flash_attn: false,
+            gpu_device: 0,
+            dtw_token_timestamps: false,
+            dtw_preset: None,
+        }
+    }
+}
+
+impl ContextParams {
+    /// Create a new set of context parameters with default values
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set whether to use the GPU
+    pub fn use_gpu(mut self, use_gpu: bool) -> Self {
+        self.use_gpu = use_gpu;
+        self
+    }
+
+    /// Set whether to use flash attention
+    ///
+    /// whisper.cpp can't compute DTW timestamps with flash attention, so
+    /// creating a context with both enabled fails.
+    pub fn flash_attn(mut self, flash_attn: bool) -> Self {
+        self.flash_attn = flash_attn;
+        self
+    }
+
+    /// Set the GPU device
+    pub fn gpu_device(mut self, device: i32) -> Self {
+        self.gpu_device = device;
+        self
+    }
+
+    /// Set whether to compute token-level timestamps with DTW
+    ///
+    /// The timestamps are reported in [`Token::dtw`](crate::Token::dtw). DTW
+    /// doesn't work with flash attention, see [`flash_attn`](Self::flash_attn).
+    pub fn dtw_token_timestamps(mut self, enabled: bool) -> Self {
+        self.dtw_token_timestamps = enabled;
+        self
+    }
+
+    /// Set the alignment heads preset for DTW
+    ///
+    /// The preset should match the model being loaded.
+    pub fn dtw_aheads_preset(mut self, preset: DtwPreset) -> Self {
+        self.dtw_preset = Some(preset);
+        self
+    }
+
+    /// Get whether to use the GPU
+    pub fn get_use_gpu(&self) -> bool {
+        self.use_gpu
+    }
+
+    /// Get whether to use flash attention
+    pub fn get_flash_attn(&self) -> bool {
+        self.flash_attn
+    }
+
+    /// Get the GPU device
+    pub fn get_gpu_device(&self) -> i32 {
+        self.gpu_device
+    }
+
+    /// Get whether DTW token-level timestamps are enabled
+    pub fn get_dtw_token_timestamps(&self) -> bool {
+        self.dtw_token_timestamps
+    }
+
+    /// Get the alignment heads preset for DTW
+    pub fn get_dtw_aheads_preset(&self) -> Option<DtwPreset> {
+        self.dtw_preset
+    }
+
+    /// Check for combinations whisper.cpp doesn't support
+    pub(crate) fn validate(&self) -> Result<()> {
+        // whisper.cpp would silently turn DTW off
+        if self.flash_attn && self.dtw_token_timestamps {
+            return Err(WhisperError::InitializationError(
+                "DTW token timestamps are not supported with flash attention".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Convert to whisper_context_params
+    pub(crate) fn to_whisper_context_params(&self) -> bindings::whisper_context_params {
+        let mut params = unsafe { bindings::whisper_context_default_params() };
+
+        params.use_gpu = self.use_gpu;
+        params.flash_attn = self.flash_attn;
+        params.gpu_device = self.gpu_device;
+        params.dtw_token_timestamps = self.dtw_token_timestamps;
+
+        if let Some(preset) = self.dtw_preset {
+            params.dtw_aheads_preset = preset.to_whisper_preset();
+            if let DtwPreset::NTopMost(n) = preset {
+                params.dtw_n_top = n;
+            }
+        }
+
+        params
+    }
+}
@@ -0,0 +1,158 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+
+use crate::commands::{run_process, Line};
+use crate::Result;
+
+// How long the binary gets to print its help or version
+const PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+// Probe results, keyed by binary path and modification time so a rebuilt
+// binary is probed again
+static PROBES: Mutex<Vec<(PathBuf, Option<SystemTime>, BinaryInfo)>> = Mutex::new(Vec::new());
+
+/// The version and supported flags of a whisper.cpp binary
+///
+/// # Examples
+///
+/// ```no_run
+/// use whisper_wrapper_rust::commands::find_whisper_binary;
+/// use whisper_wrapper_rust::BinaryInfo;
+///
+/// fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let info = BinaryInfo::probe(&find_whisper_binary()?)?;
+///     println!("whisper.cpp {}", info.get_version().unwrap_or("(unknown version)"));
+///     if !info.supports("--output-json-full") {
+///         println!("This binary is too old for token-level JSON output");
+///     }
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BinaryInfo {
+    /// Path to the binary
+    path: PathBuf,
+
+    /// The version, if the binary reports it
+    version: Option<String>,
+
+    /// Every short and long flag listed by `--help`
+    flags: BTreeSet<String>,
+}
+
+impl BinaryInfo {
+    /// Run a binary with `--help` and `--version` to find out what it supports
+    ///
+    /// Results are cached for the lifetime of the process, until the binary
+    /// is modified.
+    pub fn probe(path: &Path) -> Result<Self> {
+        let modified = fs::metadata(path).and_then(|m| m.modified()).ok();
+
+        {
+            let probes = PROBES.lock().unwrap_or_else(|e| e.into_inner());
+            if let Some((_, _, info)) = probes.iter().find(|(p, m, _)| p == path && *m == modified)
+            {
+                return Ok(info.clone());
+            }
+        }
+
+        // Run the binary without holding the lock, so a slow binary doesn't
+        // hold up probes of other binaries
+        let help = capture(path, "--help")?;
+        let version = capture(path, "--version")
+            .ok()
+            .and_then(|output| parse_version(&output))
+            .or_else(|| parse_version(&help));
+
+        let info = BinaryInfo {
+            path: path.to_path_buf(),
+            version,
+            flags: parse_flags(&help),
+        };
+
+        let mut probes = PROBES.lock().unwrap_or_else(|e| e.into_inner());
+        probes.retain(|(p, _, _)| p != path);
+        probes.push((path.to_path_buf(), modified, info.clone()));
+        Ok(info)
+    }
+
+    /// Get the path to the binary
+    pub fn get_path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Get the version, if the binary reports it
+    pub fn get_version(&self) -> Option<&str> {
+        self.version.as_deref()
+    }
+
+    /// Get every flag listed by `--help`
+    pub fn get_flags(&self) -> impl Iterator<Item = &str> {
+        self.flags.iter().map(|f| f.as_str())
+    }
+
+    /// Check whether the binary accepts a flag, such as `-ojf` or `--output-json-full`
+    pub fn supports(&self, flag: &str) -> bool {
+        self.flags.contains(flag)
+    }
+}
+
+// Run the binary with a single argument and collect everything it prints;
+// whisper.cpp prints its help to stderr and may exit with an error
+fn capture(path: &Path, arg: &str) -> Result<String> {
+    let mut cmd = Command::new(path);
+    cmd.arg(arg);
+
+    let mut output = String::new();
+    run_process(cmd, Some(PROBE_TIMEOUT), None, &mut |line| {
+        let (Line::Stdout(line) | Line::Stderr(line)) = line;
+        output.push_str(&line);
+        output.push('\n');
+    })?;
+
+    Ok(output)
+}
+
+// Collect the flags from help lines such as
+// `  -ojf,      --output-json-full  [false  ] include more information in the JSON file`
+fn parse_flags(help: &str) -> BTreeSet<String> {
+    let mut flags = BTreeSet::new();
+
+    for line in help.lines() {
+        let line = line.trim_start();
+        if !line.starts_with('-') {
+            continue;
+        }
+
+        // The flags come before the default value in brackets
+        let spec = line.split('[').next().unwrap_or_default();
+        for word in spec.split([' ', ',']) {
+            if word.starts_with('-') && word.len() > 1 {
+                flags.insert(word.to_string());
+            }
+        }
+    }
+
+    flags
+}
+
+// Find a version number such as `1.7.5` on a line mentioning the version
+fn parse_version(output: &str) -> Option<String> {
+    output
+        .lines()
+        .filter(|line| line.to_ascii_lowercase().contains("version"))
+        .flat_map(|line| line.split(|c: char| c.is_whitespace() || c == ':' || c == ','))
+        .map(|word| word.trim_start_matches('v').trim_end_matches(['.', ')']))
+        .find(|word| {
+            let parts: Vec<_> = word.split('.').collect();
+            parts.len() >= 2
+                && parts
+                    .iter()
+                    .all(|p| !p.is_empty() && p.chars().all(|c| c.is_ascii_digit()))
+        })
+        .map(|word| word.to_string())
+}
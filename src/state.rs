@@ -0,0 +1,241 @@
+use crate::bindings;
+use crate::context::WhisperContext;
+use crate::error::WhisperError;
+use crate::mel::{Mel, MIN_SAMPLES};
+use crate::transcript::TokenId;
+use crate::Result;
+
+/// A whisper.cpp state, to run the model one step at a time
+///
+/// Computes the mel spectrogram, encodes it and decodes tokens with the model
+/// of the context it was created from, see
+/// [`WhisperContext::create_state`]. The state borrows the context, so the
+/// context outlives it.
+pub struct WhisperState<'a> {
+    ctx: &'a WhisperContext,
+    state: *mut bindings::whisper_state,
+    n_threads: i32,
+
+    /// Number of tokens in the last decode, to find their logits
+    n_decoded: usize,
+}
+
+impl<'a> WhisperState<'a> {
+    /// Create a new state for a context
+    pub fn new(ctx: &'a WhisperContext) -> Result<Self> {
+        let state = unsafe { bindings::whisper_init_state(ctx.as_ptr()) };
+        if state.is_null() {
+            return Err(WhisperError::InitializationError(
+                "Failed to initialize whisper state".to_string(),
+            ));
+        }
+
+        Ok(Self {
+            ctx,
+            state,
+            // The same default as whisper.cpp
+            n_threads: std::thread::available_parallelism()
+                .map(|n| n.get().min(4) as i32)
+                .unwrap_or(1),
+            n_decoded: 0,
+        })
+    }
+
+    /// Set the number of threads to use
+    pub fn threads(mut self, n_threads: i32) -> Self {
+        self.n_threads = n_threads.max(1);
+        self
+    }
+
+    /// Get the number of threads
+    pub fn get_threads(&self) -> i32 {
+        self.n_threads
+    }
+
+    /// Get the context this state runs the model of
+    pub fn context(&self) -> &'a WhisperContext {
+        self.ctx
+    }
+
+    /// Compute the mel spectrogram of 16 kHz mono samples in the [-1, 1] range
+    ///
+    /// The spectrogram is kept in the state for [`encode`](Self::encode).
+    /// Input shorter than 201 samples is padded with silence.
+    pub fn pcm_to_mel(&mut self, samples: &[f32]) -> Result<()> {
+        let mut padded;
+        let samples = if samples.len() < MIN_SAMPLES {
+            padded = samples.to_vec();
+            padded.resize(MIN_SAMPLES, 0.0);
+            &padded[..]
+        } else {
+            samples
+        };
+
+        let status = unsafe {
+            bindings::whisper_pcm_to_mel_with_state(
+                self.ctx.as_ptr(),
+                self.state,
+                samples.as_ptr(),
+                samples.len() as i32,
+                self.n_threads,
+            )
+        };
+
+        if status != 0 {
+            return Err(WhisperError::TranscriptionError(format!(
+                "whisper_pcm_to_mel returned {}",
+                status
+            )));
+        }
+        Ok(())
+    }
+
+    /// Use a spectrogram computed elsewhere, such as with [`Mel::from_pcm`]
+    ///
+    /// The number of mel bands has to match the model, see
+    /// [`WhisperContext::n_mels`].
+    pub fn set_mel(&mut self, mel: &Mel) -> Result<()> {
+        let n_mels = self.ctx.n_mels();
+        if mel.get_n_mel() != n_mels {
+            return Err(WhisperError::TranscriptionError(format!(
+                "The model expects {} mel bands, got {}",
+                n_mels,
+                mel.get_n_mel()
+            )));
+        }
+
+        let status = unsafe {
+            bindings::whisper_set_mel_with_state(
+                self.ctx.as_ptr(),
+                self.state,
+                mel.get_data().as_ptr(),
+                mel.get_n_len() as i32,
+                mel.get_n_mel() as i32,
+            )
+        };
+
+        if status != 0 {
+            return Err(WhisperError::TranscriptionError(format!(
+                "whisper_set_mel returned {}",
+                status
+            )));
+        }
+        Ok(())
+    }
+
+    /// Get the length of the audio in the spectrogram, in frames of 10 ms
+    pub fn n_len(&self) -> usize {
+        unsafe { bindings::whisper_n_len_from_state(self.state) }.max(0) as usize
+    }
+
+    /// Run the encoder on 30 seconds of the spectrogram, starting at a frame
+    pub fn encode(&mut self, offset: usize) -> Result<()> {
+        let status = unsafe {
+            bindings::whisper_encode_with_state(
+                self.ctx.as_ptr(),
+                self.state,
+                offset.min(i32::MAX as usize) as i32,
+                self.n_threads,
+            )
+        };
+
+        if status != 0 {
+            return Err(WhisperError::TranscriptionError(format!(
+                "whisper_encode returned {}",
+                status
+            )));
+        }
+        Ok(())
+    }
+
+    /// Run the decoder on tokens following the first `n_past` tokens
+    ///
+    /// Tokens from `n_past` on are dropped from the decoder cache first, so
+    /// pass `n_past = 0` to start over. The logits of the last token are then
+    /// available from [`get_logits`](Self::get_logits).
+    pub fn decode(&mut self, tokens: &[TokenId], n_past: usize) -> Result<()> {
+        if tokens.is_empty() {
+            return Err(WhisperError::TranscriptionError(
+                "No tokens to decode".to_string(),
+            ));
+        }
+
+        let n_text_ctx = self.ctx.n_text_ctx();
+        if n_past
+            .checked_add(tokens.len())
+            .is_none_or(|n| n > n_text_ctx)
+        {
+            return Err(WhisperError::TranscriptionError(format!(
+                "{} past and {} new tokens don't fit in the model context of {} tokens",
+                n_past,
+                tokens.len(),
+                n_text_ctx
+            )));
+        }
+
+        // whisper.cpp aborts on ids outside the vocabulary
+        let n_vocab = self.ctx.n_vocab();
+        if let Some(&token) = tokens.iter().find(|&&t| t < 0 || t as usize >= n_vocab) {
+            return Err(WhisperError::InvalidToken(token));
+        }
+
+        // Logits of a failed decode can't be trusted
+        self.n_decoded = 0;
+
+        let status = unsafe {
+            bindings::whisper_decode_with_state(
+                self.ctx.as_ptr(),
+                self.state,
+                tokens.as_ptr(),
+                tokens.len() as i32,
+                n_past as i32,
+                self.n_threads,
+            )
+        };
+
+        if status != 0 {
+            return Err(WhisperError::TranscriptionError(format!(
+                "whisper_decode returned {}",
+                status
+            )));
+        }
+
+        self.n_decoded = tokens.len();
+        Ok(())
+    }
+
+    /// Get the logits of the last decoded token, one per vocabulary entry
+    ///
+    /// Empty until the first successful [`decode`](Self::decode). The slice
+    /// borrows the state, so it has to be dropped before decoding again.
+    pub fn get_logits(&self) -> &[f32] {
+        if self.n_decoded == 0 {
+            return &[];
+        }
+
+        let n_vocab = self.ctx.n_vocab();
+        let ptr = unsafe { bindings::whisper_get_logits_from_state(self.state) };
+        if ptr.is_null() {
+            return &[];
+        }
+
+        // whisper.cpp keeps a row for every decoded token but only fills the
+        // last one
+        unsafe { std::slice::from_raw_parts(ptr.add((self.n_decoded - 1) * n_vocab), n_vocab) }
+    }
+}
+
+impl Drop for WhisperState<'_> {
+    fn drop(&mut self) {
+        if !self.state.is_null() {
+            unsafe {
+                bindings::whisper_free_state(self.state);
+            }
+        }
+    }
+}
+
+// The state is only used through &mut self, or &self to read the logits, and
+// whisper.cpp allows states of one context on different threads
+unsafe impl Send for WhisperState<'_> {}
+unsafe impl Sync for WhisperState<'_> {}
@@ -0,0 +1,81 @@
+use std::ffi::CStr;
+use std::sync::Mutex;
+
+use crate::bindings;
+
+// whisper_print_system_info returns a pointer to a static buffer it rewrites
+// on every call
+static SYSTEM_INFO: Mutex<()> = Mutex::new(());
+
+/// Get the build configuration reported by whisper.cpp
+///
+/// This is the string printed by `whisper_print_system_info`, such as
+/// `WHISPER : COREML = 0 | OPENVINO = 0 | CPU : SSE3 = 1 | AVX2 = 1 | OPENMP = 1 |`.
+/// Use [`enabled_features`] for the parsed list.
+pub fn system_info() -> String {
+    let _guard = SYSTEM_INFO.lock().unwrap_or_else(|e| e.into_inner());
+    let info = unsafe { bindings::whisper_print_system_info() };
+    if info.is_null() {
+        return String::new();
+    }
+    unsafe { CStr::from_ptr(info) }
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// Get the features whisper.cpp was built with, such as `AVX2`, `OPENMP` or
+/// `COREML`
+///
+/// Features reported as disabled (`= 0`) are left out.
+pub fn enabled_features() -> Vec<String> {
+    let info = system_info();
+    let mut features = Vec::new();
+
+    for entry in info.split('|') {
+        // Each backend starts a section, as in `CPU : SSE3 = 1`
+        let entry = entry.rsplit(" : ").next().unwrap_or_default();
+        if let Some((name, value)) = entry.split_once('=') {
+            let (name, value) = (name.trim(), value.trim());
+            if !name.is_empty() && value != "0" && !features.iter().any(|f| f == name) {
+                features.push(name.to_string());
+            }
+        }
+    }
+
+    features
+}
+
+/// Get the ggml backends available to whisper.cpp, such as `CPU` or `BLAS`
+pub fn backends() -> Vec<String> {
+    let count = unsafe { bindings::ggml_backend_reg_count() };
+
+    (0..count)
+        .filter_map(|i| {
+            let name =
+                unsafe { bindings::ggml_backend_reg_name(bindings::ggml_backend_reg_get(i)) };
+            if name.is_null() {
+                return None;
+            }
+            Some(
+                unsafe { CStr::from_ptr(name) }
+                    .to_string_lossy()
+                    .into_owned(),
+            )
+        })
+        .collect()
+}
+
+/// Get the cargo features this crate was built with that change how
+/// whisper.cpp is compiled or linked
+pub fn build_features() -> Vec<&'static str> {
+    [
+        ("openblas", cfg!(feature = "openblas")),
+        ("openmp", cfg!(feature = "openmp")),
+        ("native-cpu", cfg!(feature = "native-cpu")),
+        ("static", cfg!(feature = "static")),
+    ]
+    .iter()
+    .filter(|(_, enabled)| *enabled)
+    .map(|(name, _)| *name)
+    .collect()
+}
@@ -0,0 +1,119 @@
+use std::path::{Path, PathBuf};
+
+use crate::commands::WhisperCommand;
+use crate::context::WhisperContext;
+use crate::error::WhisperError;
+use crate::params::WhisperParams;
+use crate::transcript::Transcript;
+use crate::Result;
+
+/// A speech-to-text backend
+///
+/// Implemented by [`WhisperContext`], which calls whisper.cpp in-process,
+/// and [`SubprocessTranscriber`], which runs the whisper.cpp command-line
+/// binary. Code written against this trait can switch between them, or use
+/// a mock in tests.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::path::Path;
+/// use whisper_wrapper_rust::{
+///     SubprocessTranscriber, Transcriber, WhisperContext, WhisperParams,
+/// };
+///
+/// fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let model_path = Path::new("ggml-base.bin");
+///     let use_binary = std::env::var("USE_WHISPER_BINARY").is_ok();
+///
+///     let mut transcriber: Box<dyn Transcriber> = if use_binary {
+///         Box::new(SubprocessTranscriber::new(model_path))
+///     } else {
+///         Box::new(WhisperContext::new(model_path)?)
+///     };
+///
+///     let transcript = transcriber.transcribe(Path::new("audio.wav"), &WhisperParams::new())?;
+///     println!("{}", transcript);
+///     Ok(())
+/// }
+/// ```
+pub trait Transcriber {
+    /// Transcribe an audio file
+    fn transcribe(&mut self, audio_path: &Path, params: &WhisperParams) -> Result<Transcript>;
+}
+
+impl Transcriber for WhisperContext {
+    fn transcribe(&mut self, audio_path: &Path, params: &WhisperParams) -> Result<Transcript> {
+        WhisperContext::transcribe(self, audio_path, params)
+    }
+}
+
+impl<T: Transcriber + ?Sized> Transcriber for Box<T> {
+    fn transcribe(&mut self, audio_path: &Path, params: &WhisperParams) -> Result<Transcript> {
+        (**self).transcribe(audio_path, params)
+    }
+}
+
+/// A transcriber that runs the whisper.cpp command-line binary
+///
+/// The binary is located with
+/// [`find_whisper_binary`](crate::commands::find_whisper_binary) unless a
+/// path is given. Unlike [`WhisperContext`], it accepts any audio format the
+/// binary supports.
+#[derive(Debug, Clone)]
+pub struct SubprocessTranscriber {
+    /// Path to the model file
+    model_path: PathBuf,
+
+    /// Path to the whisper.cpp binary (None to search for it)
+    binary_path: Option<PathBuf>,
+}
+
+impl SubprocessTranscriber {
+    /// Create a transcriber for a model file
+    pub fn new(model_path: &Path) -> Self {
+        Self {
+            model_path: model_path.to_path_buf(),
+            binary_path: None,
+        }
+    }
+
+    /// Set the path to the whisper.cpp binary
+    pub fn binary(mut self, binary_path: &Path) -> Self {
+        self.binary_path = Some(binary_path.to_path_buf());
+        self
+    }
+
+    /// Get the path to the model file
+    pub fn get_model_path(&self) -> &Path {
+        &self.model_path
+    }
+
+    /// Get the path to the whisper.cpp binary, if set
+    pub fn get_binary_path(&self) -> Option<&Path> {
+        self.binary_path.as_deref()
+    }
+}
+
+impl Transcriber for SubprocessTranscriber {
+    fn transcribe(&mut self, audio_path: &Path, params: &WhisperParams) -> Result<Transcript> {
+        if !self.model_path.exists() {
+            return Err(WhisperError::ModelNotFound(self.model_path.clone()));
+        }
+        if !audio_path.exists() {
+            return Err(WhisperError::AudioNotFound(audio_path.to_path_buf()));
+        }
+
+        let mut cmd = WhisperCommand::new(&self.model_path, audio_path)
+            .language(params.get_language())
+            .translate(params.get_translate());
+        if let Some(n_threads) = params.get_threads() {
+            cmd = cmd.threads(n_threads.max(1) as u32);
+        }
+        if let Some(binary_path) = &self.binary_path {
+            cmd = cmd.binary(binary_path);
+        }
+
+        cmd.transcribe()
+    }
+}
@@ -0,0 +1,279 @@
+use std::fmt;
+use std::time::Duration;
+
+use serde::Deserialize;
+
+use crate::error::WhisperError;
+use crate::Result;
+
+/// The id of a token in the model vocabulary
+pub type TokenId = i32;
+
+/// A token of a transcribed segment
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token {
+    /// The token text
+    pub text: String,
+
+    /// The token id in the model vocabulary
+    pub id: TokenId,
+
+    /// The probability of the token
+    pub p: f32,
+
+    /// Start of the token, from the beginning of the audio
+    pub start: Duration,
+
+    /// End of the token, from the beginning of the audio
+    pub end: Duration,
+
+    /// Time of the token aligned with DTW, from the beginning of the audio,
+    /// if DTW timestamps are enabled (see
+    /// [`ContextParams::dtw_token_timestamps`](crate::ContextParams::dtw_token_timestamps))
+    pub dtw: Option<Duration>,
+}
+
+/// A segment of transcribed speech
+#[derive(Debug, Clone, PartialEq)]
+pub struct Segment {
+    /// Start of the segment, from the beginning of the audio
+    pub start: Duration,
+
+    /// End of the segment, from the beginning of the audio
+    pub end: Duration,
+
+    /// The transcribed text
+    pub text: String,
+
+    /// The tokens making up the text, including special tokens such as
+    /// timestamps (empty if the backend doesn't report them)
+    pub tokens: Vec<Token>,
+}
+
+/// The result of transcribing an audio file
+///
+/// Every [`Transcriber`](crate::Transcriber) backend returns this type, so
+/// results can be handled the same way whichever backend produced them.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Transcript {
+    /// The spoken language, if the backend reports it
+    pub language: Option<String>,
+
+    /// The transcribed segments, in order
+    pub segments: Vec<Segment>,
+}
+
+impl Transcript {
+    /// Parse the JSON file written by the whisper.cpp binary with
+    /// `--output-json` or `--output-json-full`
+    pub fn from_whisper_json(json: &str) -> Result<Self> {
+        let output: JsonOutput = serde_json::from_str(json).map_err(|e| {
+            WhisperError::TranscriptionError(format!("Invalid whisper.cpp JSON output: {}", e))
+        })?;
+
+        let segments = output
+            .transcription
+            .into_iter()
+            .map(|s| Segment {
+                start: Duration::from_millis(s.offsets.from),
+                end: Duration::from_millis(s.offsets.to),
+                text: s.text,
+                tokens: s
+                    .tokens
+                    .into_iter()
+                    .map(|t| Token {
+                        text: t.text,
+                        id: t.id,
+                        p: t.p,
+                        start: Duration::from_millis(t.offsets.from),
+                        end: Duration::from_millis(t.offsets.to),
+                        // In units of 10 ms, -1 without DTW
+                        dtw: t
+                            .t_dtw
+                            .filter(|&t| t >= 0)
+                            .map(|t| Duration::from_millis(t as u64 * 10)),
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        Ok(Transcript {
+            language: output.result.and_then(|r| r.language),
+            segments,
+        })
+    }
+
+    /// Get the full transcribed text
+    pub fn text(&self) -> String {
+        self.segments
+            .iter()
+            .map(|s| s.text.trim())
+            .filter(|t| !t.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Format as plain text, one segment per line
+    pub fn to_txt(&self) -> String {
+        self.segments
+            .iter()
+            .map(|s| format!("{}\n", s.text.trim()))
+            .collect()
+    }
+
+    /// Format as SubRip subtitles
+    pub fn to_srt(&self) -> String {
+        self.segments
+            .iter()
+            .enumerate()
+            .map(|(i, s)| {
+                format!(
+                    "{}\n{} --> {}\n{}\n\n",
+                    i + 1,
+                    format_timestamp(s.start, ','),
+                    format_timestamp(s.end, ','),
+                    s.text.trim()
+                )
+            })
+            .collect()
+    }
+
+    /// Format as WebVTT subtitles
+    pub fn to_vtt(&self) -> String {
+        let mut out = String::from("WEBVTT\n\n");
+        for s in &self.segments {
+            out.push_str(&format!(
+                "{} --> {}\n{}\n\n",
+                format_timestamp(s.start, '.'),
+                format_timestamp(s.end, '.'),
+                s.text.trim()
+            ));
+        }
+        out
+    }
+
+    /// Format as JSON, with segment offsets in milliseconds
+    pub fn to_json(&self) -> String {
+        let segments: Vec<_> = self
+            .segments
+            .iter()
+            .map(|s| {
+                serde_json::json!({
+                    "start": s.start.as_millis() as u64,
+                    "end": s.end.as_millis() as u64,
+                    "text": s.text.trim(),
+                })
+            })
+            .collect();
+
+        let json = serde_json::json!({
+            "language": self.language,
+            "text": self.text(),
+            "segments": segments,
+        });
+
+        serde_json::to_string_pretty(&json).unwrap_or_default()
+    }
+
+    /// Format in one of the output formats accepted by
+    /// [`WhisperParams::output_format`](crate::WhisperParams::output_format):
+    /// txt, srt, vtt or json
+    pub fn format(&self, format: &str) -> Result<String> {
+        match format {
+            "txt" => Ok(self.to_txt()),
+            "srt" => Ok(self.to_srt()),
+            "vtt" => Ok(self.to_vtt()),
+            "json" => Ok(self.to_json()),
+            _ => Err(WhisperError::Other(format!(
+                "Unsupported output format: {}",
+                format
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for Transcript {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.text())
+    }
+}
+
+// The parts of the whisper.cpp JSON output we use
+#[derive(Deserialize)]
+struct JsonOutput {
+    result: Option<JsonResult>,
+    transcription: Vec<JsonSegment>,
+}
+
+#[derive(Deserialize)]
+struct JsonResult {
+    language: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct JsonSegment {
+    offsets: JsonOffsets,
+    text: String,
+    #[serde(default)]
+    tokens: Vec<JsonToken>,
+}
+
+#[derive(Deserialize)]
+struct JsonToken {
+    text: String,
+    id: i32,
+    p: f32,
+    offsets: JsonOffsets,
+    #[serde(default)]
+    t_dtw: Option<i64>,
+}
+
+// Offsets in milliseconds
+#[derive(Deserialize)]
+struct JsonOffsets {
+    from: u64,
+    to: u64,
+}
+
+// Format a timestamp as HH:MM:SS<sep>mmm
+fn format_timestamp(t: Duration, sep: char) -> String {
+    let ms = t.as_millis();
+    format!(
+        "{:02}:{:02}:{:02}{}{:03}",
+        ms / 3_600_000,
+        ms / 60_000 % 60,
+        ms / 1000 % 60,
+        sep,
+        ms % 1000
+    )
+}
+
+// Parse a timestamp in HH:MM:SS.mmm format
+fn parse_timestamp(s: &str) -> Option<Duration> {
+    let (hms, ms) = s.trim().split_once(['.', ','])?;
+    let mut parts = hms.split(':');
+    let h: u64 = parts.next()?.parse().ok()?;
+    let m: u64 = parts.next()?.parse().ok()?;
+    let sec: u64 = parts.next()?.parse().ok()?;
+    if parts.next().is_some() {
+        return None;
+    }
+    let ms: u64 = ms.parse().ok()?;
+
+    Some(Duration::from_millis(((h * 60 + m) * 60 + sec) * 1000 + ms))
+}
+
+// Parse a segment line printed by the whisper.cpp binary, such as
+// `[00:00:00.000 --> 00:00:05.000]   And so my fellow Americans`
+pub(crate) fn parse_segment_line(line: &str) -> Option<Segment> {
+    let rest = line.trim_start().strip_prefix('[')?;
+    let (times, text) = rest.split_once(']')?;
+    let (start, end) = times.split_once("-->")?;
+
+    Some(Segment {
+        start: parse_timestamp(start)?,
+        end: parse_timestamp(end)?,
+        text: text.trim().to_string(),
+        tokens: Vec::new(),
+    })
+}
@@ -1,4 +1,5 @@
 use std::path::Path;
+use std::time::Duration;
 use whisper_wrapper_rust::{WhisperContext, WhisperParams};
 
 #[test]
@@ -18,6 +19,37 @@ fn test_context_creation() {
     assert!(ctx.is_ok(), "Failed to create context: {:?}", ctx.err());
 }
 
+#[test]
+#[ignore] // Ignore by default as it requires a model file
+fn test_tokenizer() {
+    let model_path = Path::new("path/to/model.bin");
+
+    if !model_path.exists() {
+        println!("Skipping test as model file doesn't exist");
+        return;
+    }
+
+    let ctx = WhisperContext::new(model_path).unwrap();
+    let text = " Hello world, ça va?";
+    let tokens = ctx.tokenize(text).unwrap();
+    assert_eq!(ctx.token_count(text).unwrap(), tokens.len());
+
+    // The token bytes join back into the text
+    let mut bytes = Vec::new();
+    for &token in &tokens {
+        assert!(!ctx.is_special(token));
+        bytes.extend(ctx.token_to_bytes(token).unwrap());
+    }
+    assert_eq!(String::from_utf8(bytes).unwrap(), text);
+
+    assert!(ctx.is_special(ctx.token_eot()));
+    assert!(ctx.is_timestamp(ctx.token_beg()));
+    assert!(!ctx.is_timestamp(ctx.token_transcribe()));
+    assert!(ctx.token_lang("en").is_ok());
+    assert!(ctx.token_lang("klingon").is_err());
+    assert!(ctx.token_to_str(ctx.n_vocab() as i32).is_err());
+}
+
 #[test]
 fn test_params_creation() {
     // Test creating parameters
@@ -31,17 +63,115 @@ fn test_params_creation() {
     assert_eq!(params.get_output_format(), "txt");
 }
 
+#[test]
+fn test_params_token_timestamps() {
+    // On by default so both backends report token times
+    assert!(WhisperParams::new().get_token_timestamps());
+    assert!(!WhisperParams::new()
+        .token_timestamps(false)
+        .get_token_timestamps());
+}
+
+#[test]
+#[ignore] // Ignore by default as it requires a model file
+fn test_token_timestamps() {
+    let model_path = Path::new("path/to/model.bin");
+    let audio_path = Path::new("path/to/audio.wav");
+
+    if !model_path.exists() || !audio_path.exists() {
+        println!("Skipping test as model or audio file doesn't exist");
+        return;
+    }
+
+    let mut ctx = WhisperContext::new(model_path).unwrap();
+    let transcript = ctx
+        .transcribe(audio_path, &WhisperParams::new().language("en"))
+        .unwrap();
+
+    // Text tokens carry increasing times within their segment
+    let mut last_end = Duration::ZERO;
+    for segment in &transcript.segments {
+        for token in segment.tokens.iter().filter(|t| !ctx.is_special(t.id)) {
+            assert!(token.start <= token.end, "{:?}", token);
+            assert!(token.start >= segment.start && token.end <= segment.end);
+            assert!(token.end >= last_end, "{:?}", token);
+            last_end = token.end;
+        }
+    }
+    assert!(last_end > Duration::ZERO);
+}
+
 #[cfg(feature = "download")]
 #[test]
 #[ignore] // Ignore by default as it requires internet connection
 fn test_download_model() {
-    use whisper_wrapper_rust::download_model;
+    use whisper_wrapper_rust::{download_model, Model};
 
     // This test requires internet connection
-    let result = download_model("tiny");
+    let result = download_model(Model::Tiny);
     assert!(
         result.is_ok(),
         "Failed to download model: {:?}",
         result.err()
     );
 }
+
+#[test]
+fn test_context_from_empty_buffer() {
+    // An empty buffer is rejected before reaching whisper.cpp
+    let ctx = WhisperContext::from_buffer(&[]);
+    assert!(ctx.is_err());
+}
+
+#[test]
+fn test_context_params_creation() {
+    use whisper_wrapper_rust::{ContextParams, DtwPreset};
+
+    let params = ContextParams::new()
+        .use_gpu(false)
+        .flash_attn(false)
+        .dtw_token_timestamps(true)
+        .dtw_aheads_preset(DtwPreset::BaseEn);
+
+    assert!(!params.get_use_gpu());
+    assert!(!params.get_flash_attn());
+    assert!(params.get_dtw_token_timestamps());
+    assert_eq!(params.get_dtw_aheads_preset(), Some(DtwPreset::BaseEn));
+
+    let params = ContextParams::new().flash_attn(true);
+    assert!(params.get_flash_attn());
+}
+
+#[test]
+fn test_context_rejects_dtw_with_flash_attn() {
+    use whisper_wrapper_rust::{ContextParams, WhisperError};
+
+    // whisper.cpp would silently disable DTW, so this fails before loading
+    let params = ContextParams::new()
+        .flash_attn(true)
+        .dtw_token_timestamps(true);
+    let ctx = WhisperContext::with_params(Path::new("path/to/model.bin"), &params);
+    assert!(matches!(ctx, Err(WhisperError::InitializationError(_))));
+
+    let ctx = WhisperContext::from_buffer_with_params(&[0; 16], &params);
+    assert!(matches!(ctx, Err(WhisperError::InitializationError(_))));
+}
+
+#[test]
+fn test_model_registry() {
+    use whisper_wrapper_rust::{Model, Quantization};
+
+    let model: Model = "base.en-q5_1".parse().unwrap();
+    assert_eq!(model, Model::BaseEnQ5_1);
+    assert_eq!(model.filename(), "ggml-base.en-q5_1.bin");
+    assert_eq!(model.quantization(), Some(Quantization::Q5_1));
+    assert!(!model.is_multilingual());
+
+    // Every registry name round-trips through FromStr
+    for model in Model::all() {
+        assert_eq!(model.name().parse::<Model>().unwrap(), model);
+    }
+
+    assert_eq!("large".parse::<Model>().unwrap(), Model::LargeV3);
+    assert!("huge".parse::<Model>().is_err());
+}
@@ -0,0 +1,114 @@
+use std::fs;
+use std::thread;
+use std::time::Duration;
+
+use whisper_wrapper_rust::{ChecksumStatus, Model, ModelCache};
+
+#[test]
+fn test_cache_list_and_remove() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("ggml-base.en.bin"), vec![0u8; 100]).unwrap();
+    fs::write(dir.path().join("ggml-custom.bin"), vec![0u8; 50]).unwrap();
+    fs::write(dir.path().join("ggml-small.bin.part"), vec![0u8; 25]).unwrap();
+
+    let cache = ModelCache::with_dir(dir.path());
+    let models = cache.list().unwrap();
+    assert_eq!(models.len(), 2);
+    assert_eq!(cache.disk_usage().unwrap(), 175);
+
+    let base = cache.get("base.en").unwrap().unwrap();
+    assert_eq!(base.model, Some(Model::BaseEn));
+    assert_eq!(base.size, 100);
+    assert_eq!(cache.verify(&base).unwrap(), ChecksumStatus::Mismatch);
+
+    let custom = cache.get("custom").unwrap().unwrap();
+    assert_eq!(custom.model, None);
+    assert_eq!(cache.verify(&custom).unwrap(), ChecksumStatus::Unknown);
+
+    assert!(cache.remove("custom").unwrap().is_some());
+    assert!(cache.remove("custom").unwrap().is_none());
+    assert!(cache.remove("../escape").is_err());
+    assert_eq!(cache.list().unwrap().len(), 1);
+}
+
+#[test]
+fn test_cache_prune_least_recently_used() {
+    let dir = tempfile::tempdir().unwrap();
+    let cache = ModelCache::with_dir(dir.path());
+
+    for name in ["tiny", "base", "small"] {
+        fs::write(
+            dir.path().join(format!("ggml-{}.bin", name)),
+            vec![0u8; 100],
+        )
+        .unwrap();
+    }
+
+    // Use the models in a known order, leaving "base" as the least recently used
+    for name in ["base", "small", "tiny"] {
+        cache.touch(name).unwrap();
+        thread::sleep(Duration::from_millis(10));
+    }
+
+    let removed = cache.prune(200).unwrap();
+    assert_eq!(removed.len(), 1);
+    assert_eq!(removed[0].name, "base");
+
+    let names: Vec<_> = cache.list().unwrap().into_iter().map(|m| m.name).collect();
+    assert_eq!(names, ["tiny", "small"]);
+}
+
+#[test]
+fn test_cache_prune_counts_partial_downloads() {
+    let dir = tempfile::tempdir().unwrap();
+    let cache = ModelCache::with_dir(dir.path());
+
+    fs::write(dir.path().join("ggml-tiny.bin"), vec![0u8; 100]).unwrap();
+    fs::write(dir.path().join("ggml-base.bin"), vec![0u8; 100]).unwrap();
+    fs::write(dir.path().join("ggml-base.bin.part"), vec![0u8; 30]).unwrap();
+    fs::write(dir.path().join("ggml-small.bin.part"), vec![0u8; 40]).unwrap();
+    cache.touch("base").unwrap();
+    thread::sleep(Duration::from_millis(10));
+    cache.touch("tiny").unwrap();
+
+    // The models alone fit, but not with the partial downloads
+    let removed = cache.prune(200).unwrap();
+    assert_eq!(removed.len(), 1);
+    assert_eq!(removed[0].name, "base");
+    assert_eq!(cache.disk_usage().unwrap(), 140);
+    assert!(!dir.path().join("ggml-base.bin.part").exists());
+
+    // Partial downloads go once no model is left to remove
+    let removed = cache.prune(0).unwrap();
+    assert_eq!(removed.len(), 1);
+    assert!(!dir.path().join("ggml-small.bin.part").exists());
+    assert_eq!(cache.disk_usage().unwrap(), 0);
+}
+
+#[test]
+fn test_resolve_model_offline() {
+    use whisper_wrapper_rust::{resolve_model, set_models_dir, set_offline, WhisperError};
+
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("ggml-base.en.bin"), b"model").unwrap();
+    set_models_dir(Some(dir.path()));
+    set_offline(true);
+
+    // Cached models resolve by name, and paths are passed through
+    let path = resolve_model("base.en").unwrap();
+    assert_eq!(path, dir.path().join("ggml-base.en.bin"));
+    assert_eq!(resolve_model(path.to_str().unwrap()).unwrap(), path);
+
+    // Missing models fail without a network call
+    assert!(matches!(
+        resolve_model("tiny"),
+        Err(WhisperError::Offline(name)) if name == "tiny"
+    ));
+    assert!(matches!(
+        resolve_model("no-such-model"),
+        Err(WhisperError::UnknownModel(_))
+    ));
+
+    set_offline(false);
+    set_models_dir(None::<&std::path::Path>);
+}
@@ -0,0 +1,296 @@
+use std::ffi::OsString;
+use std::path::Path;
+use std::time::Duration;
+
+use whisper_wrapper_rust::{OutputFormat, WhisperCommand, WhisperEvent};
+
+#[test]
+fn test_whisper_command_args() {
+    let cmd = WhisperCommand::new(Path::new("ggml-base.bin"), Path::new("audio.wav"))
+        .language("de")
+        .threads(8)
+        .offset(Duration::from_millis(1500))
+        .beam_size(5)
+        .prompt("Meeting notes")
+        .output_file(Path::new("out/audio"))
+        .output_format(OutputFormat::Srt)
+        .output_format(OutputFormat::JsonFull)
+        .output_format(OutputFormat::Srt)
+        .diarize(true)
+        .arg("--suppress-nst");
+
+    let expected: Vec<OsString> = [
+        "-m",
+        "ggml-base.bin",
+        "-f",
+        "audio.wav",
+        "-l",
+        "de",
+        "-t",
+        "8",
+        "-ot",
+        "1500",
+        "-bs",
+        "5",
+        "--prompt",
+        "Meeting notes",
+        "-of",
+        "out/audio",
+        "--diarize",
+        "--output-srt",
+        "--output-json-full",
+        "--suppress-nst",
+    ]
+    .iter()
+    .map(OsString::from)
+    .collect();
+
+    assert_eq!(cmd.to_args(), expected);
+}
+
+#[test]
+fn test_output_format_from_str() {
+    assert_eq!(
+        "json-full".parse::<OutputFormat>().unwrap(),
+        OutputFormat::JsonFull
+    );
+    assert_eq!(OutputFormat::JsonFull.extension(), "json");
+    assert!("docx".parse::<OutputFormat>().is_err());
+}
+
+// Write a shell script standing in for whisper-cli
+#[cfg(unix)]
+fn fake_binary(dir: &Path, script: &str) -> std::path::PathBuf {
+    use std::os::unix::fs::PermissionsExt;
+
+    let binary = dir.join("whisper-cli");
+    std::fs::write(&binary, format!("#!/bin/sh\n{}", script)).unwrap();
+    std::fs::set_permissions(&binary, std::fs::Permissions::from_mode(0o755)).unwrap();
+    binary
+}
+
+#[cfg(unix)]
+fn fake_command(binary: &Path) -> WhisperCommand {
+    WhisperCommand::new(Path::new("model.bin"), Path::new("audio.wav")).binary(binary)
+}
+
+#[cfg(unix)]
+#[test]
+fn test_whisper_command_reports_failure() {
+    use whisper_wrapper_rust::WhisperError;
+
+    let dir = tempfile::tempdir().unwrap();
+    let binary = fake_binary(dir.path(), "echo 'error: unknown argument' >&2\nexit 3\n");
+
+    let result = fake_command(&binary).run();
+
+    match result {
+        Err(WhisperError::CommandError(message)) => {
+            assert!(message.contains("unknown argument"), "{}", message)
+        }
+        other => panic!("expected a command error, got {:?}", other),
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn test_whisper_command_streams_events() {
+    use whisper_wrapper_rust::WhisperEvent;
+
+    let dir = tempfile::tempdir().unwrap();
+    let binary = fake_binary(
+        dir.path(),
+        "echo 'whisper_full_with_state: auto-detected language: en (p = 0.965163)' >&2\n\
+         echo '[00:00:00.000 --> 00:00:02.500]   Hello there.'\n\
+         echo 'whisper_print_progress_callback: progress =  50%' >&2\n\
+         echo '[00:00:02.500 --> 00:00:04.000]   General Kenobi.'\n\
+         echo 'whisper_print_timings: total time = 1.00 ms' >&2\n",
+    );
+
+    let mut events = Vec::new();
+    let transcript = fake_command(&binary).stream(|e| events.push(e)).unwrap();
+
+    assert_eq!(transcript.language.as_deref(), Some("en"));
+    assert_eq!(transcript.text(), "Hello there. General Kenobi.");
+    assert_eq!(transcript.segments[1].end, Duration::from_secs(4));
+
+    // stdout and stderr are read separately, so only the order within each
+    // stream is guaranteed
+    assert!(events.contains(&WhisperEvent::Language("en".to_string())));
+    assert!(events.contains(&WhisperEvent::Progress(50)));
+    assert!(events.contains(&WhisperEvent::Stderr(
+        "whisper_print_timings: total time = 1.00 ms".to_string()
+    )));
+    let segments: Vec<_> = events
+        .iter()
+        .filter(|e| matches!(e, WhisperEvent::Segment(_)))
+        .collect();
+    assert_eq!(segments.len(), 2);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_whisper_command_timeout() {
+    use std::time::Instant;
+    use whisper_wrapper_rust::WhisperError;
+
+    let dir = tempfile::tempdir().unwrap();
+    let binary = fake_binary(dir.path(), "exec sleep 10\n");
+
+    let started = Instant::now();
+    let result = fake_command(&binary)
+        .timeout(Duration::from_millis(200))
+        .run();
+
+    assert!(matches!(result, Err(WhisperError::Timeout(_))));
+    assert!(started.elapsed() < Duration::from_secs(5));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_whisper_command_cancel() {
+    use std::time::Instant;
+    use whisper_wrapper_rust::{CancelHandle, WhisperError};
+
+    let dir = tempfile::tempdir().unwrap();
+    let binary = fake_binary(dir.path(), "echo started\nexec sleep 10\n");
+
+    let cancel = CancelHandle::new();
+    let canceller = cancel.clone();
+
+    // Cancel from another thread once the process is running
+    let started = Instant::now();
+    let result = fake_command(&binary)
+        .cancel_handle(&cancel)
+        .stream(move |event| {
+            if event == WhisperEvent::Stdout("started".to_string()) {
+                let canceller = canceller.clone();
+                std::thread::spawn(move || canceller.cancel());
+            }
+        });
+
+    assert!(matches!(result, Err(WhisperError::Cancelled)));
+    assert!(started.elapsed() < Duration::from_secs(5));
+}
+
+// Help text in the layout printed by whisper-cli, without some newer flags
+#[cfg(unix)]
+const OLD_HELP: &str = "\
+usage: whisper-cli [options] file0.wav file1.wav ...
+
+options:
+  -h,        --help              [default] show this help message and exit
+  -t N,      --threads N         [4      ] number of threads to use during computation
+  -tr,       --translate         [false  ] translate from source language to english
+  -oj,       --output-json       [false  ] output result in a JSON file
+  -l LANG,   --language LANG     [en     ] spoken language ('auto' for auto-detect)
+  -m FNAME,  --model FNAME       [models/ggml-base.en.bin] model path
+  -f FNAME,  --file FNAME        [       ] input WAV file path
+";
+
+#[cfg(unix)]
+fn fake_versioned_binary(dir: &Path) -> std::path::PathBuf {
+    fake_binary(
+        dir,
+        &format!(
+            "case \"$1\" in\n\
+             --help) cat >&2 <<'HELP'\n{}HELP\n;;\n\
+             --version) echo 'whisper.cpp version: v1.5.4' >&2; exit 1 ;;\n\
+             esac\n",
+            OLD_HELP
+        ),
+    )
+}
+
+#[cfg(unix)]
+#[test]
+fn test_probe_binary() {
+    use whisper_wrapper_rust::BinaryInfo;
+
+    let dir = tempfile::tempdir().unwrap();
+    let binary = fake_versioned_binary(dir.path());
+
+    let info = BinaryInfo::probe(&binary).unwrap();
+
+    assert_eq!(info.get_path(), binary);
+    assert_eq!(info.get_version(), Some("1.5.4"));
+    assert!(info.supports("-t"));
+    assert!(info.supports("--threads"));
+    assert!(info.supports("-oj"));
+    assert!(!info.supports("--output-json-full"));
+    assert!(!info.supports("N"));
+
+    // The second probe is served from the cache
+    assert_eq!(BinaryInfo::probe(&binary).unwrap(), info);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_slow_probe_does_not_block_others() {
+    use std::time::{Duration, Instant};
+    use whisper_wrapper_rust::BinaryInfo;
+
+    let slow_dir = tempfile::tempdir().unwrap();
+    let slow = fake_binary(slow_dir.path(), "[ \"$1\" = --help ] && sleep 3\n");
+    let dir = tempfile::tempdir().unwrap();
+    let binary = fake_versioned_binary(dir.path());
+
+    let slow_probe = std::thread::spawn(move || BinaryInfo::probe(&slow));
+    std::thread::sleep(Duration::from_millis(200));
+
+    let start = Instant::now();
+    let info = BinaryInfo::probe(&binary).unwrap();
+    assert!(
+        start.elapsed() < Duration::from_secs(2),
+        "{:?}",
+        start.elapsed()
+    );
+    assert_eq!(info.get_version(), Some("1.5.4"));
+
+    slow_probe.join().unwrap().unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn test_check_flags_adapts_and_refuses() {
+    use whisper_wrapper_rust::WhisperError;
+
+    let dir = tempfile::tempdir().unwrap();
+    let binary = fake_versioned_binary(dir.path());
+
+    // Full JSON output falls back to plain JSON on older binaries
+    let cmd = fake_command(&binary)
+        .threads(2)
+        .output_format(OutputFormat::JsonFull)
+        .check_flags(true)
+        .to_command()
+        .unwrap();
+    let args: Vec<_> = cmd.get_args().collect();
+    assert_eq!(
+        args,
+        [
+            "-m",
+            "model.bin",
+            "-f",
+            "audio.wav",
+            "-t",
+            "2",
+            "--output-json"
+        ]
+    );
+
+    // Options the binary doesn't know are refused before it runs
+    let result = fake_command(&binary).diarize(true).check_flags(true).run();
+    match result {
+        Err(e @ WhisperError::UnsupportedFlag { .. }) => {
+            let message = e.to_string();
+            assert!(message.contains("--diarize"), "{}", message);
+            assert!(message.contains("1.5.4"), "{}", message);
+        }
+        other => panic!("expected an unsupported flag error, got {:?}", other),
+    }
+
+    // Without checking, the preferred spellings are passed through
+    let cmd = fake_command(&binary).diarize(true).to_command().unwrap();
+    assert!(cmd.get_args().any(|a| a == "--diarize"));
+}
@@ -0,0 +1,67 @@
+#![cfg(unix)]
+
+use std::env;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+
+use whisper_wrapper_rust::commands::{find_whisper_binary, WHISPER_CPP_BIN_ENV};
+use whisper_wrapper_rust::WhisperError;
+
+fn write_file(path: &Path, mode: u32) {
+    std::fs::write(path, "#!/bin/sh\n").unwrap();
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode)).unwrap();
+}
+
+// The lookups depend on process-wide environment variables, so they are
+// checked in a single test
+#[test]
+fn test_find_whisper_binary() {
+    let dir = tempfile::tempdir().unwrap();
+    let empty = dir.path().join("empty");
+    let legacy = dir.path().join("legacy");
+    let modern = dir.path().join("modern");
+    for d in [&empty, &legacy, &modern] {
+        std::fs::create_dir(d).unwrap();
+    }
+
+    // A file without the executable bit is skipped
+    write_file(&empty.join("whisper-cli"), 0o644);
+    write_file(&legacy.join("main"), 0o755);
+    write_file(&modern.join("whisper-cli"), 0o755);
+    // openai-whisper's Python script, which takes other arguments
+    write_file(&empty.join("whisper"), 0o755);
+
+    env::remove_var(WHISPER_CPP_BIN_ENV);
+
+    // whisper-cli is preferred over the legacy name, wherever it is in PATH
+    env::set_var("PATH", env::join_paths([&empty, &legacy, &modern]).unwrap());
+    assert_eq!(find_whisper_binary().unwrap(), modern.join("whisper-cli"));
+
+    env::set_var("PATH", env::join_paths([&empty, &legacy]).unwrap());
+    assert_eq!(find_whisper_binary().unwrap(), legacy.join("main"));
+
+    // The override wins, and must point to an executable
+    env::set_var(WHISPER_CPP_BIN_ENV, legacy.join("main"));
+    env::set_var("PATH", env::join_paths([&modern]).unwrap());
+    assert_eq!(find_whisper_binary().unwrap(), legacy.join("main"));
+
+    env::set_var(WHISPER_CPP_BIN_ENV, empty.join("whisper-cli"));
+    assert!(matches!(
+        find_whisper_binary(),
+        Err(WhisperError::BinaryNotFound { .. })
+    ));
+    env::remove_var(WHISPER_CPP_BIN_ENV);
+
+    // The error lists every location tried, and `whisper` isn't one of them
+    env::set_var("PATH", env::join_paths([&empty]).unwrap());
+    match find_whisper_binary() {
+        Err(e @ WhisperError::BinaryNotFound { .. }) => {
+            let message = e.to_string();
+            assert!(message.contains(&empty.join("whisper-cli").display().to_string()));
+            assert!(message.contains(&empty.join("main").display().to_string()));
+        }
+        // A system-wide install can't be ruled out
+        Ok(path) => assert!(!path.starts_with(&empty)),
+        Err(e) => panic!("unexpected error: {}", e),
+    }
+}
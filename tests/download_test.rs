@@ -0,0 +1,302 @@
+#![cfg(feature = "download")]
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+use whisper_wrapper_rust::{download_custom_model, download_url, set_models_dir, DownloadConfig};
+
+// Serializes tests that change the global models directory or offline mode
+static GLOBALS: Mutex<()> = Mutex::new(());
+
+// Serve `body` on a local port. If `cut_at` is set, the first connection is
+// dropped after that many bytes; connections honour `Range: bytes=N-` requests.
+fn serve(body: Vec<u8>, cut_at: Option<usize>, connections: usize) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    thread::spawn(move || {
+        for (i, stream) in listener.incoming().take(connections).enumerate() {
+            let mut stream = stream.unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+            let mut start = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                let line = line.trim_end();
+                if line.is_empty() {
+                    break;
+                }
+                if let Some(range) = line.to_ascii_lowercase().strip_prefix("range: bytes=") {
+                    start = range.trim_end_matches('-').parse().unwrap();
+                }
+            }
+
+            let rest = &body[start..];
+            let header = if start > 0 {
+                format!(
+                    "HTTP/1.1 206 Partial Content\r\nContent-Length: {}\r\nContent-Range: bytes {}-{}/{}\r\nConnection: close\r\n\r\n",
+                    rest.len(),
+                    start,
+                    body.len() - 1,
+                    body.len()
+                )
+            } else {
+                format!(
+                    "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                    rest.len()
+                )
+            };
+            stream.write_all(header.as_bytes()).unwrap();
+
+            match cut_at {
+                // Drop the connection mid-transfer
+                Some(cut_at) if i == 0 => stream.write_all(&rest[..cut_at]).unwrap(),
+                _ => stream.write_all(rest).unwrap(),
+            }
+        }
+    });
+
+    format!("http://{}/ggml-test.bin", addr)
+}
+
+// Accept connections on a local port but never answer them
+#[cfg(feature = "async-download")]
+fn serve_silent() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    thread::spawn(move || {
+        let mut streams = Vec::new();
+        for stream in listener.incoming() {
+            streams.push(stream.unwrap());
+        }
+    });
+
+    format!("http://{}/ggml-test.bin", addr)
+}
+
+#[test]
+fn test_download_resumes_after_interruption() {
+    let _guard = GLOBALS.lock().unwrap();
+    let body: Vec<u8> = (0..200_000u32).map(|i| (i % 251) as u8).collect();
+    let url = serve(body.clone(), Some(50_000), 2);
+
+    let dir = tempfile::tempdir().unwrap();
+    let dest = dir.path().join("ggml-test.bin");
+    let part = dir.path().join("ggml-test.bin.part");
+
+    // The first attempt is cut off and leaves only the partial file behind
+    let no_retries = DownloadConfig::new().retries(0);
+    let first = download_url(&url, &dest, &no_retries, |_| {});
+    assert!(first.is_err());
+    assert!(!dest.exists());
+    assert_eq!(std::fs::metadata(&part).unwrap().len(), 50_000);
+
+    // The second attempt resumes from the partial file
+    let mut last = None;
+    download_url(&url, &dest, &no_retries, |p| last = Some(p)).unwrap();
+    assert!(!part.exists());
+    assert_eq!(std::fs::read(&dest).unwrap(), body);
+
+    let last = last.unwrap();
+    assert_eq!(last.downloaded, body.len() as u64);
+    assert_eq!(last.total, Some(body.len() as u64));
+}
+
+#[test]
+fn test_download_retries_after_interruption() {
+    let _guard = GLOBALS.lock().unwrap();
+    let body: Vec<u8> = (0..100_000u32).map(|i| (i % 239) as u8).collect();
+    let url = serve(body.clone(), Some(30_000), 2);
+
+    let dir = tempfile::tempdir().unwrap();
+    let dest = dir.path().join("ggml-test.bin");
+
+    let config = DownloadConfig::new()
+        .retries(1)
+        .backoff(Duration::from_millis(10));
+    download_url(&url, &dest, &config, |_| {}).unwrap();
+    assert_eq!(std::fs::read(&dest).unwrap(), body);
+}
+
+#[test]
+fn test_download_config_backoff() {
+    let config = DownloadConfig::default();
+    assert_eq!(config.get_retries(), 3);
+    assert_eq!(config.get_backoff(), Duration::from_secs(1));
+    assert!(config.get_user_agent().starts_with("whisper_wrapper_rust/"));
+    assert_eq!(config.get_proxy(), None);
+}
+
+#[test]
+fn test_download_models_reports_each_result() {
+    use whisper_wrapper_rust::{download_models, set_offline, Model, WhisperError};
+
+    let _guard = GLOBALS.lock().unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("ggml-tiny.bin"), b"cached").unwrap();
+    set_models_dir(Some(dir.path()));
+    set_offline(true);
+
+    // One model is cached, the other can't be fetched offline; neither
+    // outcome stops the other and duplicates are dropped
+    let results = download_models(
+        &[Model::Tiny, Model::Base, Model::Tiny],
+        &DownloadConfig::default(),
+    );
+
+    set_offline(false);
+    set_models_dir(None::<&std::path::Path>);
+
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0].0, Model::Tiny);
+    assert_eq!(
+        results[0].1.as_ref().unwrap(),
+        &dir.path().join("ggml-tiny.bin")
+    );
+    assert_eq!(results[1].0, Model::Base);
+    assert!(matches!(results[1].1, Err(WhisperError::Offline(_))));
+}
+
+#[test]
+fn test_download_url_offline() {
+    use whisper_wrapper_rust::{set_offline, WhisperError};
+
+    let _guard = GLOBALS.lock().unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    set_models_dir(Some(dir.path()));
+    set_offline(true);
+
+    // Nothing listens on this port, so any request would fail differently
+    let url = "http://127.0.0.1:9/ggml-test.bin";
+    let dest = dir.path().join("ggml-test.bin");
+    let direct = download_url(url, &dest, &DownloadConfig::default(), |_| {});
+    let custom = download_custom_model("fine-tuned", url, &DownloadConfig::default());
+
+    set_offline(false);
+    set_models_dir(None::<&std::path::Path>);
+
+    assert!(matches!(direct, Err(WhisperError::Offline(u)) if u == url));
+    assert!(matches!(custom, Err(WhisperError::Offline(_))));
+    assert!(!dir.path().join("ggml-test.bin.part").exists());
+}
+
+#[test]
+fn test_verify_model_detects_mismatch() {
+    use whisper_wrapper_rust::{verify_model, Model, WhisperError};
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("ggml-tiny.bin");
+    std::fs::write(&path, b"not a model").unwrap();
+
+    match verify_model(Model::Tiny, &path) {
+        Err(WhisperError::ChecksumMismatch {
+            expected, actual, ..
+        }) => {
+            assert_eq!(expected, Model::Tiny.sha1());
+            // SHA-1 of "not a model"
+            assert_eq!(actual, "400eaec69ae19ae8c19ec4c795b0c9252315a07e");
+        }
+        other => panic!("expected a checksum mismatch, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_download_custom_model_into_models_dir() {
+    let body = b"fine-tuned model".to_vec();
+    let url = serve(body.clone(), None, 1);
+
+    let _guard = GLOBALS.lock().unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    set_models_dir(Some(dir.path()));
+
+    let path = download_custom_model("fine-tuned", &url, &DownloadConfig::default()).unwrap();
+    assert_eq!(path, dir.path().join("ggml-fine-tuned.bin"));
+    assert_eq!(std::fs::read(&path).unwrap(), body);
+
+    set_models_dir(None::<&std::path::Path>);
+}
+
+#[cfg(feature = "async-download")]
+#[tokio::test]
+#[allow(clippy::await_holding_lock)] // Only keeps offline mode from changing
+async fn test_async_download_resumes_after_interruption() {
+    use whisper_wrapper_rust::download_url_async;
+
+    let _guard = GLOBALS.lock().unwrap();
+
+    let body: Vec<u8> = (0..200_000u32).map(|i| (i % 241) as u8).collect();
+    let url = serve(body.clone(), Some(80_000), 2);
+
+    let dir = tempfile::tempdir().unwrap();
+    let dest = dir.path().join("ggml-test.bin");
+
+    let no_retries = DownloadConfig::new().retries(0);
+    assert!(download_url_async(&url, &dest, &no_retries, None)
+        .await
+        .is_err());
+    assert!(!dest.exists());
+
+    let (tx, mut rx) = tokio::sync::mpsc::channel(1024);
+    download_url_async(&url, &dest, &no_retries, Some(&tx))
+        .await
+        .unwrap();
+    assert_eq!(std::fs::read(&dest).unwrap(), body);
+
+    // The first update reflects the bytes already on disk
+    let first = rx.recv().await.unwrap();
+    assert_eq!(first.downloaded, 80_000);
+    assert_eq!(first.total, Some(body.len() as u64));
+}
+
+#[cfg(feature = "async-download")]
+#[tokio::test]
+#[allow(clippy::await_holding_lock)] // Only keeps offline mode from changing
+async fn test_async_download_model_uses_cache() {
+    use whisper_wrapper_rust::{download_model_async, set_offline, Model, WhisperError};
+
+    let _guard = GLOBALS.lock().unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("ggml-tiny.bin"), b"cached").unwrap();
+    set_models_dir(Some(dir.path()));
+    set_offline(true);
+
+    let config = DownloadConfig::default();
+    let cached = download_model_async(Model::Tiny, &config, None).await;
+    let missing = download_model_async(Model::Base, &config, None).await;
+
+    set_offline(false);
+    set_models_dir(None::<&std::path::Path>);
+
+    assert_eq!(cached.unwrap(), dir.path().join("ggml-tiny.bin"));
+    assert!(matches!(missing, Err(WhisperError::Offline(_))));
+}
+
+#[cfg(feature = "async-download")]
+#[tokio::test]
+#[allow(clippy::await_holding_lock)] // Only keeps offline mode from changing
+async fn test_async_download_times_out_without_response() {
+    use whisper_wrapper_rust::{download_url_async, WhisperError};
+
+    let _guard = GLOBALS.lock().unwrap();
+    let url = serve_silent();
+    let dir = tempfile::tempdir().unwrap();
+    let dest = dir.path().join("ggml-test.bin");
+
+    let config = DownloadConfig::new()
+        .retries(0)
+        .read_timeout(Duration::from_millis(200));
+    let result = tokio::time::timeout(
+        Duration::from_secs(10),
+        download_url_async(&url, &dest, &config, None),
+    )
+    .await
+    .expect("the download should time out on its own");
+
+    assert!(matches!(result, Err(WhisperError::DownloadError(_))));
+    assert!(!dest.exists());
+}
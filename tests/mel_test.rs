@@ -0,0 +1,112 @@
+use std::f32::consts::PI;
+use std::path::Path;
+use whisper_wrapper_rust::{Mel, WhisperContext};
+
+fn sine(freq: f32, seconds: f32) -> Vec<f32> {
+    (0..(16000.0 * seconds) as usize)
+        .map(|i| 0.5 * (2.0 * PI * freq * i as f32 / 16000.0).sin())
+        .collect()
+}
+
+#[test]
+fn test_mel_shape() {
+    // One frame every 10 ms, with 30 seconds of padding
+    let mel = Mel::from_pcm(&sine(440.0, 1.0), 80);
+    assert_eq!(mel.get_n_mel(), 80);
+    assert_eq!(mel.get_n_len(), 3100);
+    assert_eq!(mel.get_data().len(), 80 * 3100);
+    assert!(mel.get(79, 3099).is_some());
+    assert!(mel.get(80, 0).is_none());
+    assert!(mel.get(0, 3100).is_none());
+
+    let mel = Mel::from_pcm(&sine(440.0, 1.0), 128);
+    assert_eq!(mel.get_n_mel(), 128);
+
+    // Very short input is padded
+    let mel = Mel::from_pcm(&[0.1; 10], 80);
+    assert_eq!(mel.get_n_len(), 3001);
+}
+
+#[test]
+fn test_mel_values() {
+    let mel = Mel::from_pcm(&sine(1000.0, 1.0), 80);
+
+    // 1 kHz falls in band 26 of 80
+    let frame: Vec<f32> = (0..80).map(|band| mel.get(band, 50).unwrap()).collect();
+    let peak = (0..80)
+        .max_by(|&a, &b| frame[a].total_cmp(&frame[b]))
+        .unwrap();
+    assert_eq!(peak, 26);
+
+    // Values span 80 dB, scaled down by 4, and the padding is at the floor
+    let max = mel.get_data().iter().copied().fold(f32::MIN, f32::max);
+    let min = mel.get_data().iter().copied().fold(f32::MAX, f32::min);
+    assert!((max - min - 2.0).abs() < 1e-5, "{} {}", min, max);
+    assert_eq!(mel.get(40, 3000), Some(min));
+
+    // Silence is flat
+    let silence = Mel::from_pcm(&[0.0; 16000], 80);
+    assert!(silence.get_data().iter().all(|&v| v == -1.5));
+}
+
+#[test]
+fn test_mel_new() {
+    let mel = Mel::new(vec![0.0; 80 * 10], 80).unwrap();
+    assert_eq!(mel.get_n_len(), 10);
+    assert_eq!(mel.clone().into_data().len(), 800);
+
+    assert!(Mel::new(vec![0.0; 81], 80).is_err());
+    assert!(Mel::new(vec![], 0).is_err());
+}
+
+#[test]
+#[ignore] // Ignore by default as it requires a model file
+fn test_state_matches_rust_mel() {
+    let model_path = Path::new("path/to/model.bin");
+
+    if !model_path.exists() {
+        println!("Skipping test as model file doesn't exist");
+        return;
+    }
+
+    let ctx = WhisperContext::new(model_path).unwrap();
+    let samples = sine(440.0, 3.0);
+    let prompt = [
+        ctx.token_sot(),
+        ctx.token_lang("en").unwrap(),
+        ctx.token_transcribe(),
+    ];
+
+    // Decode the same prompt from whisper.cpp's spectrogram and from ours
+    let mut state = ctx.create_state().unwrap();
+    assert!(state.get_logits().is_empty());
+    state.pcm_to_mel(&samples).unwrap();
+    state.encode(0).unwrap();
+    state.decode(&prompt, 0).unwrap();
+    let expected = state.get_logits().to_vec();
+    assert_eq!(expected.len(), ctx.n_vocab());
+
+    let mut state = ctx.create_state().unwrap().threads(2);
+    state
+        .set_mel(&Mel::from_pcm(&samples, ctx.n_mels()))
+        .unwrap();
+    state.encode(0).unwrap();
+    state.decode(&prompt, 0).unwrap();
+    let max_diff = state
+        .get_logits()
+        .iter()
+        .zip(&expected)
+        .map(|(a, b)| (a - b).abs())
+        .fold(0.0, f32::max);
+    assert!(max_diff < 1e-2, "{}", max_diff);
+
+    // Decoding continues after the prompt
+    state.decode(&[ctx.token_beg()], prompt.len()).unwrap();
+    assert_eq!(state.get_logits().len(), ctx.n_vocab());
+
+    assert!(state.set_mel(&Mel::from_pcm(&samples, 7)).is_err());
+    assert!(state.decode(&[], 0).is_err());
+    assert!(state.decode(&[ctx.n_vocab() as i32], 0).is_err());
+    assert!(state.decode(&prompt, ctx.n_text_ctx()).is_err());
+    assert!(state.decode(&prompt, usize::MAX).is_err());
+}
@@ -0,0 +1,27 @@
+use whisper_wrapper_rust::{backends, build_features, enabled_features, system_info};
+
+#[test]
+fn test_system_info() {
+    let info = system_info();
+    assert!(info.contains("WHISPER"), "{}", info);
+
+    // Every enabled feature comes from the reported string
+    for feature in enabled_features() {
+        assert!(info.contains(&format!("{} = ", feature)), "{}", feature);
+        assert!(!info.contains(&format!("{} = 0", feature)), "{}", feature);
+    }
+
+    assert!(backends().iter().any(|b| b == "CPU"));
+}
+
+#[test]
+fn test_build_features() {
+    assert_eq!(
+        build_features().contains(&"openmp"),
+        cfg!(feature = "openmp")
+    );
+    assert_eq!(
+        build_features().contains(&"static"),
+        cfg!(feature = "static")
+    );
+}
@@ -0,0 +1,205 @@
+use std::path::Path;
+use std::time::Duration;
+
+use whisper_wrapper_rust::{
+    read_wav, Segment, Transcriber, Transcript, WhisperError, WhisperParams,
+};
+
+// Build a WAV file from 16-bit samples
+fn wav(sample_rate: u32, channels: u16, samples: &[i16]) -> Vec<u8> {
+    let data: Vec<u8> = samples.iter().flat_map(|s| s.to_le_bytes()).collect();
+
+    let mut out = Vec::new();
+    out.extend_from_slice(b"RIFF");
+    out.extend_from_slice(&(36 + data.len() as u32).to_le_bytes());
+    out.extend_from_slice(b"WAVEfmt ");
+    out.extend_from_slice(&16u32.to_le_bytes());
+    out.extend_from_slice(&1u16.to_le_bytes());
+    out.extend_from_slice(&channels.to_le_bytes());
+    out.extend_from_slice(&sample_rate.to_le_bytes());
+    out.extend_from_slice(&(sample_rate * channels as u32 * 2).to_le_bytes());
+    out.extend_from_slice(&(channels * 2).to_le_bytes());
+    out.extend_from_slice(&16u16.to_le_bytes());
+    out.extend_from_slice(b"data");
+    out.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    out.extend_from_slice(&data);
+    out
+}
+
+fn sample_transcript() -> Transcript {
+    Transcript {
+        language: Some("en".to_string()),
+        segments: vec![
+            Segment {
+                start: Duration::from_millis(0),
+                end: Duration::from_millis(2500),
+                text: " Hello there.".to_string(),
+                tokens: vec![],
+            },
+            Segment {
+                start: Duration::from_millis(2500),
+                end: Duration::from_millis(3_723_004),
+                text: " General Kenobi.".to_string(),
+                tokens: vec![],
+            },
+        ],
+    }
+}
+
+#[test]
+fn test_transcript_formats() {
+    let transcript = sample_transcript();
+
+    assert_eq!(transcript.text(), "Hello there. General Kenobi.");
+    assert_eq!(
+        transcript.format("srt").unwrap(),
+        "1\n00:00:00,000 --> 00:00:02,500\nHello there.\n\n\
+         2\n00:00:02,500 --> 01:02:03,004\nGeneral Kenobi.\n\n"
+    );
+    assert!(transcript
+        .format("vtt")
+        .unwrap()
+        .starts_with("WEBVTT\n\n00:00:00.000 --> 00:00:02.500\nHello there.\n"));
+    assert!(transcript.format("docx").is_err());
+}
+
+#[test]
+fn test_read_wav_mixes_down_to_mono() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("stereo.wav");
+    std::fs::write(&path, wav(16000, 2, &[16384, 0, -16384, -16384])).unwrap();
+
+    assert_eq!(read_wav(&path).unwrap(), vec![0.25, -0.5]);
+
+    // Streamed files don't know their sizes up front
+    let mut streamed = wav(16000, 1, &[16384, -16384]);
+    streamed[4..8].copy_from_slice(&u32::MAX.to_le_bytes());
+    streamed[40..44].copy_from_slice(&u32::MAX.to_le_bytes());
+    std::fs::write(&path, streamed).unwrap();
+    assert_eq!(read_wav(&path).unwrap(), vec![0.5, -0.5]);
+
+    // Resampling is left to the caller
+    std::fs::write(&path, wav(44100, 1, &[0, 0])).unwrap();
+    assert!(matches!(
+        read_wav(&path),
+        Err(WhisperError::UnsupportedAudioFormat(_))
+    ));
+}
+
+// A backend that returns a canned transcript, as an application test would
+struct MockTranscriber;
+
+impl Transcriber for MockTranscriber {
+    fn transcribe(
+        &mut self,
+        _audio_path: &Path,
+        _params: &WhisperParams,
+    ) -> Result<Transcript, WhisperError> {
+        Ok(sample_transcript())
+    }
+}
+
+#[test]
+fn test_mock_transcriber() {
+    let mut transcriber: Box<dyn Transcriber> = Box::new(MockTranscriber);
+    let transcript = transcriber
+        .transcribe(Path::new("audio.wav"), &WhisperParams::new())
+        .unwrap();
+    assert_eq!(transcript.segments.len(), 2);
+}
+
+// Trimmed output of `whisper-cli -ojf`
+const WHISPER_JSON: &str = r#"{
+    "systeminfo": "AVX = 1 | AVX2 = 1",
+    "model": {"type": "base", "multilingual": true},
+    "params": {"model": "ggml-base.bin", "language": "auto", "translate": false},
+    "result": {"language": "en"},
+    "transcription": [
+        {
+            "timestamps": {"from": "00:00:00,000", "to": "00:00:02,500"},
+            "offsets": {"from": 0, "to": 2500},
+            "text": " Hello there.",
+            "tokens": [
+                {"text": "[_BEG_]", "timestamps": {"from": "00:00:00,000", "to": "00:00:00,000"}, "offsets": {"from": 0, "to": 0}, "id": 50364, "p": 0.98, "t_dtw": -1},
+                {"text": " Hello", "timestamps": {"from": "00:00:00,000", "to": "00:00:01,200"}, "offsets": {"from": 0, "to": 1200}, "id": 2425, "p": 0.91, "t_dtw": -1},
+                {"text": " there.", "timestamps": {"from": "00:00:01,200", "to": "00:00:02,500"}, "offsets": {"from": 1200, "to": 2500}, "id": 456, "p": 0.87, "t_dtw": 183}
+            ]
+        },
+        {
+            "timestamps": {"from": "00:00:02,500", "to": "00:00:04,000"},
+            "offsets": {"from": 2500, "to": 4000},
+            "text": " General Kenobi.",
+            "tokens": []
+        }
+    ]
+}"#;
+
+#[test]
+fn test_transcript_from_whisper_json() {
+    let transcript = Transcript::from_whisper_json(WHISPER_JSON).unwrap();
+
+    assert_eq!(transcript.language.as_deref(), Some("en"));
+    assert_eq!(transcript.text(), "Hello there. General Kenobi.");
+    assert_eq!(transcript.segments[1].start, Duration::from_millis(2500));
+
+    let token = &transcript.segments[0].tokens[1];
+    assert_eq!(token.text, " Hello");
+    assert_eq!(token.id, 2425);
+    assert_eq!(token.end, Duration::from_millis(1200));
+
+    // DTW times are in units of 10 ms, -1 when DTW is off
+    assert_eq!(token.dtw, None);
+    let token = &transcript.segments[0].tokens[2];
+    assert_eq!(token.dtw, Some(Duration::from_millis(1830)));
+
+    assert!(Transcript::from_whisper_json("{}").is_err());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_subprocess_transcriber_reads_json_output() {
+    use std::os::unix::fs::PermissionsExt;
+    use whisper_wrapper_rust::SubprocessTranscriber;
+
+    let dir = tempfile::tempdir().unwrap();
+    let model = dir.path().join("ggml-test.bin");
+    let audio = dir.path().join("audio.wav");
+    let json = dir.path().join("output.json");
+    let of_log = dir.path().join("of.txt");
+    std::fs::write(&model, b"model").unwrap();
+    std::fs::write(&audio, b"audio").unwrap();
+    std::fs::write(&json, WHISPER_JSON).unwrap();
+
+    // A stand-in for whisper-cli that writes canned JSON to `-of <path>.json`
+    // and records the path it was given
+    let binary = dir.path().join("whisper-cli");
+    std::fs::write(
+        &binary,
+        format!(
+            "#!/bin/sh\n\
+             while [ $# -gt 0 ]; do\n\
+             [ \"$1\" = -of ] && out=\"$2\"\n\
+             shift\n\
+             done\n\
+             echo \"$out\" > '{}'\n\
+             cp '{}' \"$out.json\"\n",
+            of_log.display(),
+            json.display()
+        ),
+    )
+    .unwrap();
+    std::fs::set_permissions(&binary, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+    let mut transcriber = SubprocessTranscriber::new(&model).binary(&binary);
+    let transcript = transcriber
+        .transcribe(&audio, &WhisperParams::new())
+        .unwrap();
+    assert_eq!(
+        transcript,
+        Transcript::from_whisper_json(WHISPER_JSON).unwrap()
+    );
+
+    // The temporary output is cleaned up
+    let output_file = std::fs::read_to_string(&of_log).unwrap();
+    assert!(!Path::new(&format!("{}.json", output_file.trim())).exists());
+}